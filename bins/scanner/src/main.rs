@@ -1,12 +1,16 @@
-use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use anyhow::{Context, Result};
 use ai_core::{
     cfg::{self, AppId},
-    job::{Action, JobSpec, JobState},
+    job::{find_cycle, Action, ExecResult, JobSpec, JobState, RunHistory, Schedule, Trigger},
     logx,
     // ✅ add Kv so get/put/delete methods resolve
     store::{open_default, DefaultKv, Kv, KvSerde, ns},
 };
 use clap::{Args, Parser, Subcommand};
+use serde::Deserialize;
 use serde_json::{json, to_string_pretty};
 use tracing::info;
 
@@ -50,6 +54,12 @@ enum Command {
         #[command(subcommand)]
         cmd: JobsCmd,
     },
+
+    /// Remote-agent assignment operations (server side of the `agent` feature)
+    Agents {
+        #[command(subcommand)]
+        cmd: AgentsCmd,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -60,10 +70,59 @@ enum JobsCmd {
     AddKvPut(AddKvPut),
     /// Add/replace an external process exec job
     AddExec(AddExec),
+    /// Add/replace a sandboxed Lua script job
+    AddLua(AddLua),
+    /// Add/replace an outbound HTTP job
+    AddHttp(AddHttp),
     /// Delete job by id
     Del {
         id: String,
     },
+    /// Show recent run history for a job
+    History {
+        id: String,
+        /// Max number of most-recent results to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Show the latest captured result for a job
+    Result {
+        id: String,
+    },
+    /// Print the job dependency DAG as DOT
+    Graph,
+}
+
+/// Dependency/trigger flags shared by all `jobs add-*` subcommands.
+#[derive(Args, Debug)]
+struct DepsArgs {
+    /// Parent job ids this job depends on (for cycle detection and `jobs graph`)
+    #[arg(long)]
+    depends_on: Vec<String>,
+    /// Trigger kind: "schedule" (default) | "on_success" | "on_completion"
+    #[arg(long, default_value = "schedule")]
+    trigger: String,
+    /// Parent job id to gate on; required unless --trigger is "schedule"
+    #[arg(long)]
+    trigger_parent: Option<String>,
+    /// Pin this job to a remote agent id; omit to broadcast to any agent
+    #[arg(long)]
+    assigned_to: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum AgentsCmd {
+    /// Show all registered agents with their computed liveness (mirrors `GET /agents`)
+    List,
+    /// Show jobs currently assigned to an agent (assigned + broadcast)
+    Jobs {
+        id: String,
+    },
+    /// Pin an existing job to an agent by id (mirrors `POST /agents/:id/jobs`)
+    Assign {
+        agent_id: String,
+        job_id: String,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -78,6 +137,14 @@ struct AddKvPut {
     decode: String,
     #[arg(long, default_value_t = 1000)]
     period_ms: u64,
+    /// Per-job jitter added to period_ms, stable across restarts
+    #[arg(long, default_value_t = 0)]
+    jitter_ms: u64,
+    /// 5-field cron expression ("min hour dom month dow"); overrides period_ms/jitter_ms
+    #[arg(long)]
+    cron: Option<String>,
+    #[command(flatten)]
+    deps: DepsArgs,
 }
 
 #[derive(Args, Debug)]
@@ -92,6 +159,94 @@ struct AddExec {
     timeout_ms: Option<u64>,
     #[arg(long, default_value_t = 1000)]
     period_ms: u64,
+    /// Per-job jitter added to period_ms, stable across restarts
+    #[arg(long, default_value_t = 0)]
+    jitter_ms: u64,
+    /// 5-field cron expression ("min hour dom month dow"); overrides period_ms/jitter_ms
+    #[arg(long)]
+    cron: Option<String>,
+    /// Treat a non-zero exit as an expected flake: record it, but don't escalate backoff
+    #[arg(long, default_value_t = false)]
+    tolerate_nonzero_exit: bool,
+    #[command(flatten)]
+    deps: DepsArgs,
+}
+
+#[derive(Args, Debug)]
+struct AddLua {
+    id: String,
+    /// Lua script source; exposes kv_get/kv_put/kv_del/log
+    #[arg(long)]
+    script: String,
+    #[arg(long)]
+    timeout_ms: Option<u64>,
+    #[arg(long, default_value_t = 1000)]
+    period_ms: u64,
+    /// Per-job jitter added to period_ms, stable across restarts
+    #[arg(long, default_value_t = 0)]
+    jitter_ms: u64,
+    /// 5-field cron expression ("min hour dom month dow"); overrides period_ms/jitter_ms
+    #[arg(long)]
+    cron: Option<String>,
+    #[command(flatten)]
+    deps: DepsArgs,
+}
+
+#[derive(Args, Debug)]
+struct AddHttp {
+    id: String,
+    #[arg(long)]
+    url: String,
+    /// HTTP method (GET by default). One of GET/POST/PUT/DELETE
+    #[arg(long)]
+    method: Option<String>,
+    /// Extra request header "Name: value"; pass multiple
+    #[arg(long = "header", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+    /// JSON request body, e.g. '{"k":"v"}'
+    #[arg(long)]
+    body: Option<String>,
+    /// Response status required for success; defaults to any 2xx
+    #[arg(long)]
+    expect_status: Option<u16>,
+    /// KV key to store the response body bytes into
+    #[arg(long)]
+    store_to: Option<String>,
+    #[arg(long)]
+    timeout_ms: Option<u64>,
+    #[arg(long, default_value_t = 1000)]
+    period_ms: u64,
+    /// Per-job jitter added to period_ms, stable across restarts
+    #[arg(long, default_value_t = 0)]
+    jitter_ms: u64,
+    /// 5-field cron expression ("min hour dom month dow"); overrides period_ms/jitter_ms
+    #[arg(long)]
+    cron: Option<String>,
+    #[command(flatten)]
+    deps: DepsArgs,
+}
+
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (k, v) = s.split_once(':').ok_or_else(|| format!("expected 'Name: value', got '{s}'"))?;
+    Ok((k.trim().to_string(), v.trim().to_string()))
+}
+
+fn schedule_from_args(cron: Option<String>, period_ms: u64, jitter_ms: u64) -> Schedule {
+    match cron {
+        Some(expr) => Schedule::Cron(expr),
+        None => Schedule::Every { period_ms, jitter_ms },
+    }
+}
+
+fn trigger_from_args(deps: &DepsArgs) -> Result<Trigger> {
+    match deps.trigger.as_str() {
+        "schedule" => Ok(Trigger::OnSchedule),
+        "on_success" => deps.trigger_parent.clone().map(Trigger::OnSuccessOf)
+            .ok_or_else(|| anyhow::anyhow!("--trigger-parent is required for --trigger on_success")),
+        "on_completion" => deps.trigger_parent.clone().map(Trigger::OnCompletionOf)
+            .ok_or_else(|| anyhow::anyhow!("--trigger-parent is required for --trigger on_completion")),
+        other => Err(anyhow::anyhow!("unknown --trigger '{other}' (expected schedule|on_success|on_completion)")),
+    }
 }
 
 fn app_by_name(name: &str) -> &'static AppId {
@@ -118,34 +273,135 @@ fn list_jobs(kv: &impl KvSerde) -> Result<Vec<serde_json::Value>> {
 }
 
 fn upsert_job(kv: &impl KvSerde, id: &str, spec: JobSpec) -> Result<()> {
-    let reg_key = ns("jobs", "registry");
-    let mut ids: Vec<String> = kv.get_t(&reg_key)?.unwrap_or_default();
-    if !ids.iter().any(|s| s == id) {
-        ids.push(id.to_string());
-        kv.put_t(&reg_key, &ids)?;
+    if let Some(cycle) = job_cycle(kv, id, &spec.effective_depends_on())? {
+        anyhow::bail!("dependency cycle: {}", cycle.join(" -> "));
     }
+
+    let id_owned = id.to_string();
+    kv.update_t::<Vec<String>>(&ns("jobs", "registry"), |cur| {
+        let mut ids = cur.unwrap_or_default();
+        if !ids.iter().any(|s| s == &id_owned) { ids.push(id_owned.clone()); }
+        ids
+    })?;
     kv.put_t(&ns("jobs", &format!("{}:spec", id)), &spec)?;
     // reset state to start fresh
     kv.put_t(&ns("jobs", &format!("{}:state", id)), &JobState::default())?;
     Ok(())
 }
 
+/// Builds the job-registry's dependency edge map (every job's
+/// [`JobSpec::effective_depends_on`], so a trigger-only dependency is seen
+/// too) and checks whether setting `id`'s edges to `new_depends_on` would
+/// introduce a cycle. Returns the cycle (`id -> ... -> id`) if so.
+fn job_cycle(kv: &impl KvSerde, id: &str, new_depends_on: &[String]) -> Result<Option<Vec<String>>> {
+    let ids: Vec<String> = kv.get_t(&ns("jobs", "registry"))?.unwrap_or_default();
+    let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+    for existing_id in &ids {
+        if existing_id == id {
+            continue;
+        }
+        let spec: Option<JobSpec> = kv.get_t(&ns("jobs", &format!("{existing_id}:spec")))?;
+        edges.insert(existing_id.clone(), spec.map(|s| s.effective_depends_on()).unwrap_or_default());
+    }
+    Ok(find_cycle(&edges, id, new_depends_on))
+}
+
 fn del_job(kv: &impl KvSerde, id: &str) -> Result<()> {
     // remove spec/state
     let _ = kv.delete(&ns("jobs", &format!("{}:spec", id)));
     let _ = kv.delete(&ns("jobs", &format!("{}:state", id)));
-    // prune from registry (typed read, then write if present)
-    let reg_key = ns("jobs", "registry");
-    if let Some(mut list) = kv.get_t::<Vec<String>>(&reg_key)? {
-        list.retain(|s| s != id);
-        kv.put_t(&reg_key, &list)?;
-    }
+    // prune from registry; CAS-looped so concurrent deletes can't lose each other's entries
+    let id_owned = id.to_string();
+    kv.update_t::<Vec<String>>(&ns("jobs", "registry"), |cur| {
+        let mut ids = cur.unwrap_or_default();
+        ids.retain(|s| s != &id_owned);
+        ids
+    })?;
     Ok(())
 }
 
+fn job_history(kv: &impl KvSerde, id: &str, limit: usize) -> Result<Vec<serde_json::Value>> {
+    let hist: RunHistory = kv.get_t(&ns("jobs", &format!("{id}:history")))?.unwrap_or_default();
+    let skip = hist.results.len().saturating_sub(limit);
+    Ok(hist.results[skip..].iter().map(|r| json!(r)).collect())
+}
+
+fn job_result(kv: &impl KvSerde, id: &str) -> Result<Option<ExecResult>> {
+    kv.get_t(&ns("jobs", &format!("{id}:result")))
+}
+
+/// All registered agents with their computed liveness. Mirrors `GET /agents`.
+fn agent_list(kv: &impl KvSerde) -> Result<Vec<serde_json::Value>> {
+    // Same field/variant layout as the server's bincode-stored agent records.
+    #[derive(Deserialize, serde::Serialize)]
+    struct AgentRecord { id: String, hostname: String, capabilities: Vec<String>, registered_at_ms: u64 }
+    #[derive(Deserialize, serde::Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum AgentLiveness { New, Active, Idle, Offline }
+    #[derive(Deserialize, serde::Serialize)]
+    struct AgentLivenessState { state: AgentLiveness, last_seen_ms: u64 }
+
+    let ids: Vec<String> = kv.get_t(&ns("agents", "registry"))?.unwrap_or_default();
+    let mut out = Vec::new();
+    for id in ids {
+        let record: Option<AgentRecord> = kv.get_t(&ns("agents", &id))?;
+        let Some(record) = record else { continue };
+        let state: AgentLivenessState = kv.get_t(&ns("agents", &format!("{id}:state")))?
+            .unwrap_or(AgentLivenessState { state: AgentLiveness::New, last_seen_ms: 0 });
+        out.push(json!({ "record": record, "liveness": state }));
+    }
+    Ok(out)
+}
+
+/// Jobs assigned to `agent_id`: every registry job whose spec's `assigned_to`
+/// is this agent, plus unassigned (`None`) jobs, which broadcast to any agent.
+/// Mirrors the server's `GET /agents/:id/jobs` derivation.
+fn agent_jobs(kv: &impl KvSerde, agent_id: &str) -> Result<Vec<serde_json::Value>> {
+    let ids: Vec<String> = kv.get_t(&ns("jobs", "registry"))?.unwrap_or_default();
+    let mut out = Vec::new();
+    for id in ids {
+        let spec: Option<JobSpec> = kv.get_t(&ns("jobs", &format!("{id}:spec")))?;
+        let Some(spec) = spec else { continue };
+        match &spec.assigned_to {
+            Some(target) if target == agent_id => out.push(json!({ "id": id, "spec": spec })),
+            None => out.push(json!({ "id": id, "spec": spec })),
+            Some(_) => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Renders the job dependency DAG as a DOT graph, using every job's
+/// [`JobSpec::effective_depends_on`] edges (so trigger parents are included),
+/// each labelled with its trigger kind or `depends_on`.
+fn job_graph_dot(kv: &impl KvSerde) -> Result<String> {
+    let ids: Vec<String> = kv.get_t(&ns("jobs", "registry"))?.unwrap_or_default();
+    let mut out = String::from("digraph sentinel_jobs {\n");
+    for id in &ids {
+        let _ = writeln!(out, "  \"{id}\";");
+    }
+    for id in &ids {
+        let spec: Option<JobSpec> = kv.get_t(&ns("jobs", &format!("{id}:spec")))?;
+        let Some(spec) = spec else { continue };
+        for parent in &spec.effective_depends_on() {
+            let label = match spec.trigger.parent() {
+                Some(p) if p == parent.as_str() => match &spec.trigger {
+                    Trigger::OnSuccessOf(_) => "on_success",
+                    Trigger::OnCompletionOf(_) => "on_completion",
+                    Trigger::OnSchedule => unreachable!(),
+                },
+                _ => "depends_on",
+            };
+            let _ = writeln!(out, "  \"{parent}\" -> \"{id}\" [label=\"{label}\"];");
+        }
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    logx::init("info");
+    let _log_guard = logx::init("info", None, "daily");
 
     let app = app_by_name(&cli.app);
     let kv = open_kv(app)?;
@@ -178,26 +434,73 @@ fn main() -> Result<()> {
         }
 
         Command::Jobs { cmd: JobsCmd::AddKvPut(args) } => {
+            let trigger = trigger_from_args(&args.deps)?;
             let spec = JobSpec {
-                period_ms: args.period_ms,
+                schedule: schedule_from_args(args.cron, args.period_ms, args.jitter_ms),
                 action: Action::KvPut {
                     key: args.key,
                     decode: args.decode,
                     value: serde_json::Value::String(args.value),
                 },
+                depends_on: args.deps.depends_on,
+                trigger,
+                tolerate_nonzero_exit: false,
+                assigned_to: args.deps.assigned_to,
             };
             upsert_job(&kv, &args.id, spec)?;
             println!("ok");
         }
 
         Command::Jobs { cmd: JobsCmd::AddExec(args) } => {
+            let trigger = trigger_from_args(&args.deps)?;
             let spec = JobSpec {
-                period_ms: args.period_ms,
+                schedule: schedule_from_args(args.cron, args.period_ms, args.jitter_ms),
                 action: Action::Exec {
                     cmd: args.cmd,
                     args: args.args,
                     timeout_ms: args.timeout_ms,
                 },
+                depends_on: args.deps.depends_on,
+                trigger,
+                tolerate_nonzero_exit: args.tolerate_nonzero_exit,
+                assigned_to: args.deps.assigned_to,
+            };
+            upsert_job(&kv, &args.id, spec)?;
+            println!("ok");
+        }
+
+        Command::Jobs { cmd: JobsCmd::AddLua(args) } => {
+            let trigger = trigger_from_args(&args.deps)?;
+            let spec = JobSpec {
+                schedule: schedule_from_args(args.cron, args.period_ms, args.jitter_ms),
+                action: Action::Lua { script: args.script, timeout_ms: args.timeout_ms },
+                depends_on: args.deps.depends_on,
+                trigger,
+                tolerate_nonzero_exit: false,
+                assigned_to: args.deps.assigned_to,
+            };
+            upsert_job(&kv, &args.id, spec)?;
+            println!("ok");
+        }
+
+        Command::Jobs { cmd: JobsCmd::AddHttp(args) } => {
+            let trigger = trigger_from_args(&args.deps)?;
+            let body = args.body.map(|b| serde_json::from_str(&b)).transpose().context("--body must be valid JSON")?;
+            let spec = JobSpec {
+                schedule: schedule_from_args(args.cron, args.period_ms, args.jitter_ms),
+                action: Action::Http {
+                    url: args.url,
+                    method: args.method,
+                    headers: args.headers,
+                    body,
+                    timeout_ms: args.timeout_ms,
+                    expect_status: args.expect_status,
+                    store_to: args.store_to,
+                },
+                depends_on: args.deps.depends_on,
+                trigger,
+                tolerate_nonzero_exit: false,
+                assigned_to: args.deps.assigned_to,
             };
             upsert_job(&kv, &args.id, spec)?;
             println!("ok");
@@ -207,6 +510,40 @@ fn main() -> Result<()> {
             del_job(&kv, &id)?;
             println!("ok");
         }
+
+        Command::Jobs { cmd: JobsCmd::History { id, limit } } => {
+            let results = job_history(&kv, &id, limit)?;
+            println!("{}", to_string_pretty(&results)?);
+        }
+
+        Command::Jobs { cmd: JobsCmd::Result { id } } => {
+            match job_result(&kv, &id)? {
+                Some(result) => println!("{}", to_string_pretty(&result)?),
+                None => println!("null"),
+            }
+        }
+
+        Command::Jobs { cmd: JobsCmd::Graph } => {
+            print!("{}", job_graph_dot(&kv)?);
+        }
+
+        Command::Agents { cmd: AgentsCmd::List } => {
+            let agents = agent_list(&kv)?;
+            println!("{}", to_string_pretty(&agents)?);
+        }
+
+        Command::Agents { cmd: AgentsCmd::Jobs { id } } => {
+            let jobs = agent_jobs(&kv, &id)?;
+            println!("{}", to_string_pretty(&jobs)?);
+        }
+
+        Command::Agents { cmd: AgentsCmd::Assign { agent_id, job_id } } => {
+            let spec_key = ns("jobs", &format!("{job_id}:spec"));
+            let mut spec: JobSpec = kv.get_t(&spec_key)?.ok_or_else(|| anyhow::anyhow!("no such job '{job_id}'"))?;
+            spec.assigned_to = Some(agent_id);
+            kv.put_t(&spec_key, &spec)?;
+            println!("ok");
+        }
     }
 
     Ok(())
@@ -0,0 +1,132 @@
+#![cfg(feature = "agent")]
+
+use std::time::Duration;
+
+use ai_core::job::{ExecResult, JobSpec};
+use serde::{Deserialize, Serialize};
+use tokio::time::interval;
+use tracing::{info, warn};
+
+use crate::module::{Module, ModuleCtx};
+use crate::runner::execute;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct AgentJob {
+    id: String,
+    spec: JobSpec,
+}
+
+#[derive(Serialize)]
+struct AgentResultBody {
+    job_id: String,
+    result: ExecResult,
+}
+
+/// What this agent reports about itself on registration.
+#[derive(Serialize)]
+struct AgentRegisterBody {
+    hostname: String,
+    capabilities: Vec<String>,
+}
+
+/// Action kinds this build of the agent can execute, reported at registration
+/// so the server (or an operator via `scanner agents assign`) knows what's
+/// safe to pin here.
+fn capabilities() -> Vec<String> {
+    vec!["noop".into(), "kv_put".into(), "kv_del".into(), "exec".into(), "http".into(), "lua".into()]
+}
+
+/// Polls a sentinel server for assigned jobs, runs them locally, and reports results back.
+///
+/// This is the "agent" side of the agent/server topology: the server holds the
+/// job registry, this module just executes whatever it's assigned and phones
+/// home with the outcome.
+pub struct Agent {
+    server_url: String,
+    agent_id: String,
+    /// Bearer token presented on every request, if the server requires one.
+    token: Option<String>,
+    poll_period: Duration,
+}
+
+impl Agent {
+    pub fn new(server_url: String, agent_id: String, token: Option<String>, poll_period: Duration) -> Self {
+        Self { server_url, agent_id, token, poll_period }
+    }
+
+    /// Applies the configured bearer token to a request, if any.
+    fn authed(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(t) => req.bearer_auth(t),
+            None => req,
+        }
+    }
+}
+
+impl Module for Agent {
+    fn name(&self) -> &'static str { "agent" }
+
+    fn spawn(self: Box<Self>, mut ctx: ModuleCtx) -> tokio::task::JoinHandle<anyhow::Result<()>> {
+        tokio::spawn(async move {
+            let client = reqwest::Client::new();
+
+            let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+            let register_body = AgentRegisterBody { hostname, capabilities: capabilities() };
+            let register = self
+                .authed(client.post(format!("{}/agents/{}/register", self.server_url, self.agent_id)))
+                .json(&register_body);
+            if let Err(e) = register.send().await {
+                warn!("agent registration failed: {}", e);
+            }
+
+            let mut tick = interval(self.poll_period);
+            loop {
+                tokio::select! {
+                    _ = tick.tick() => {
+                        if let Err(e) = self.poll_and_run(&client, &ctx).await {
+                            warn!("agent poll failed: {}", e);
+                        }
+                    }
+                    changed = ctx.shutdown.changed() => {
+                        if changed.is_ok() && *ctx.shutdown.borrow() {
+                            info!("agent stopping");
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Agent {
+    async fn poll_and_run(&self, client: &reqwest::Client, ctx: &ModuleCtx) -> anyhow::Result<()> {
+        let list = self.authed(client.get(format!("{}/agents/{}/jobs", self.server_url, self.agent_id)));
+        let jobs: Vec<AgentJob> = list.send().await?.error_for_status()?.json().await?;
+
+        for job in jobs {
+            let result = match execute(&job.spec.action, &ctx.kv).await {
+                Ok(exec) => exec,
+                Err(e) => {
+                    warn!("agent job failed id={} err={}", job.id, e);
+                    ExecResult {
+                        exit_code: None,
+                        stdout: String::new(),
+                        stderr: e.to_string(),
+                        duration_ms: 0,
+                        finished_at_ms: 0,
+                    }
+                }
+            };
+            let body = AgentResultBody { job_id: job.id.clone(), result };
+            let report = self
+                .authed(client.post(format!("{}/agents/{}/results", self.server_url, self.agent_id)))
+                .json(&body);
+            if let Err(e) = report.send().await.and_then(reqwest::Response::error_for_status) {
+                warn!("agent result report failed id={} err={}", job.id, e);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+#![cfg(feature = "client")]
+
+//! Typed async client for the [`crate::web::WebServer`] HTTP API, for
+//! embedders and integration tests that would otherwise have to hand-roll
+//! requests and re-derive the response shapes. Every method mirrors one
+//! handler 1:1 and returns the same wire types the server serializes.
+
+use ai_core::job::{ExecResult, JobSpec, JobState};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+
+/// Base URL plus optional rustls client identity for talking to a `WebServer`
+/// (including its HTTPS listener, if it requires client certs).
+pub struct ClientConfig {
+    pub base_url: String,
+    pub tls_client_cert_pem: Option<String>,
+    pub tls_client_key_pem: Option<String>,
+}
+
+impl ClientConfig {
+    /// A plain config with no client TLS identity.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self { base_url: base_url.into(), tls_client_cert_pem: None, tls_client_key_pem: None }
+    }
+
+    /// Present this PEM cert/key pair to the server as a client TLS identity.
+    pub fn with_client_cert(mut self, cert_pem: String, key_pem: String) -> Self {
+        self.tls_client_cert_pem = Some(cert_pem);
+        self.tls_client_key_pem = Some(key_pem);
+        self
+    }
+}
+
+/// Error shape returned by a failed `WebServer` request.
+#[derive(Error, Debug)]
+pub enum ApiError {
+    /// The request itself failed (connect, TLS, timeout, ...).
+    #[error("request failed: {0}")]
+    Request(#[from] reqwest::Error),
+    /// The server answered with a non-2xx `(StatusCode, msg)` body.
+    #[error("server error ({status}): {message}")]
+    Server { status: u16, message: String },
+    /// The response body didn't decode as the expected type.
+    #[error("failed to decode response: {0}")]
+    Decode(String),
+}
+
+/// Envelope every [`Client`] method returns.
+pub type ApiResult<T> = Result<T, ApiError>;
+
+/// Mirrors `web::Status`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Status {
+    pub heartbeat_count: u64,
+    pub uptime_ms: u64,
+    pub failing_jobs: Vec<FailingJob>,
+}
+
+/// Mirrors `web::FailingJob`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FailingJob {
+    pub id: String,
+    pub failures: u64,
+    pub error_kind: Option<String>,
+    pub error_msg: Option<String>,
+}
+
+/// Mirrors `web::JobView`.
+#[derive(Clone, Deserialize)]
+pub struct JobView {
+    pub id: String,
+    pub spec: JobSpec,
+    pub state: JobState,
+    pub last_result: Option<ExecResult>,
+}
+
+/// Mirrors `web::JobUpsertLegacy`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JobUpsertLegacy {
+    pub id: String,
+    pub cmd: String,
+    pub period_ms: u64,
+}
+
+/// Mirrors `web::JobUpsertNew`.
+#[derive(Clone, Serialize)]
+pub struct JobUpsertNew {
+    pub id: String,
+    pub spec: JobSpec,
+}
+
+/// Mirrors `web::JobUpsertEither`: body of `POST /jobs`.
+#[derive(Clone, Serialize)]
+#[serde(untagged)]
+pub enum JobUpsertEither {
+    Legacy(JobUpsertLegacy),
+    New(JobUpsertNew),
+}
+
+/// Typed async client mirroring the handlers exposed by [`crate::web::WebServer`].
+pub struct Client {
+    http: reqwest::Client,
+    cfg: ClientConfig,
+}
+
+impl Client {
+    /// Build a client, installing `cfg`'s client TLS identity if present.
+    pub fn new(cfg: ClientConfig) -> anyhow::Result<Self> {
+        let mut builder = reqwest::Client::builder();
+        if let (Some(cert), Some(key)) = (&cfg.tls_client_cert_pem, &cfg.tls_client_key_pem) {
+            let mut pem = cert.clone();
+            pem.push_str(key);
+            builder = builder.identity(reqwest::Identity::from_pem(pem.as_bytes())?);
+        }
+        Ok(Self { http: builder.build()?, cfg })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.cfg.base_url, path)
+    }
+
+    /// Sends `req`, decoding a 2xx body as `T` or mapping a non-2xx response
+    /// into `ApiError::Server` the way every `WebServer` handler's
+    /// `(StatusCode, msg)` error shape implies.
+    async fn send<T: for<'de> Deserialize<'de>>(&self, req: reqwest::RequestBuilder) -> ApiResult<T> {
+        let resp = req.send().await?;
+        let status = resp.status();
+        if status.is_success() {
+            let bytes = resp.bytes().await?;
+            serde_json::from_slice(&bytes).map_err(|e| ApiError::Decode(e.to_string()))
+        } else {
+            let message = resp.text().await.unwrap_or_default();
+            Err(ApiError::Server { status: status.as_u16(), message })
+        }
+    }
+
+    pub async fn status(&self) -> ApiResult<Status> {
+        self.send(self.http.get(self.url("/status"))).await
+    }
+
+    pub async fn kv_get(&self, key: &str, decode: Option<&str>) -> ApiResult<serde_json::Value> {
+        let mut req = self.http.get(self.url(&format!("/kv/{key}")));
+        if let Some(d) = decode {
+            req = req.query(&[("decode", d)]);
+        }
+        self.send(req).await
+    }
+
+    /// `PUT /kv/:key` answers with a plain-text `ok`, not JSON, so only the status is checked.
+    pub async fn kv_put(&self, key: &str, decode: &str, value: serde_json::Value) -> ApiResult<()> {
+        let body = json!({ "decode": decode, "value": value });
+        let resp = self.http.put(self.url(&format!("/kv/{key}"))).json(&body).send().await?;
+        let status = resp.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let message = resp.text().await.unwrap_or_default();
+            Err(ApiError::Server { status: status.as_u16(), message })
+        }
+    }
+
+    pub async fn kv_del(&self, key: &str) -> ApiResult<serde_json::Value> {
+        self.send(self.http.delete(self.url(&format!("/kv/{key}")))).await
+    }
+
+    pub async fn jobs_list(&self) -> ApiResult<Vec<JobView>> {
+        self.send(self.http.get(self.url("/jobs"))).await
+    }
+
+    pub async fn jobs_upsert(&self, body: JobUpsertEither) -> ApiResult<serde_json::Value> {
+        self.send(self.http.post(self.url("/jobs")).json(&body)).await
+    }
+
+    pub async fn jobs_delete(&self, id: &str) -> ApiResult<serde_json::Value> {
+        self.send(self.http.delete(self.url(&format!("/jobs/{id}")))).await
+    }
+}
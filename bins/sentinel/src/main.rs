@@ -3,10 +3,11 @@ use std::time::Duration;
 use anyhow::Result;
 use ai_core::{
     cfg::{self, AppId},
-    job::{Action, JobSpec},
+    job::{Action, JobSpec, Schedule},
     logx,
     store::{open_default, DefaultKv, KvSerde, ns},
 };
+use clap::Parser;
 use tokio::signal;
 use tracing::{info, warn};
 
@@ -14,20 +15,43 @@ mod module;
 mod heartbeat;
 mod scheduler;
 mod runner;
+mod notifier;
+#[cfg(feature = "agent")]
+mod agent;
 #[cfg(feature = "web-api")]
 mod web;
+#[cfg(feature = "client")]
+mod client;
 
 use crate::heartbeat::Heartbeat;
 use crate::module::{Module, ModuleCtx};
+use crate::notifier::Notifier;
 use crate::scheduler::Scheduler;
 
 const APP: AppId = AppId { qualifier: "com", organization: "local", application: "sentinel" };
 
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Cli {
+    /// Also run as a remote agent polling this control-plane server URL for
+    /// assigned jobs, alongside the local scheduler (requires the `agent` feature).
+    #[arg(long)]
+    agent_server: Option<String>,
+    /// Agent id to register/poll as (defaults to the local hostname).
+    #[arg(long)]
+    agent_id: Option<String>,
+    /// Bearer token to present to `--agent-server` (must match its `[agent]` config).
+    #[arg(long)]
+    agent_token: Option<String>,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // ---- init cfg/log/kv
     let conf = cfg::load_or_init(&APP)?;
-    logx::init(&conf.log_level);
+    let _log_guard = logx::init(&conf.log_level, conf.log_dir.as_deref(), &conf.log_rotation);
 
     let cfgdir = cfg::config_dir(&APP)?;
     let kv: DefaultKv = open_default(cfgdir.join("kv"))?;
@@ -43,32 +67,65 @@ async fn main() -> Result<()> {
 
     // ---- modules
     let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let (events_tx, _) = tokio::sync::broadcast::channel::<ai_core::job::JobEvent>(256);
     let mut tasks = Vec::new();
 
-    // Heartbeat @ 1s
-    let hb = Box::new(Heartbeat::new(Duration::from_millis(1000)));
-    info!("module start: {}", hb.name());
-    tasks.push(hb.spawn(ModuleCtx {
+    let ctx = |events: &tokio::sync::broadcast::Sender<ai_core::job::JobEvent>| ModuleCtx {
         kv: kv.clone(),
         shutdown: shutdown_rx.clone(),
-    }));
+        events: events.clone(),
+    };
 
-    // Scheduler @ 250ms tick, concurrency = num_cpus
-    let sch = Box::new(Scheduler::new(250, num_cpus::get()));
+    // Heartbeat @ 1s
+    let hb = Box::new(Heartbeat::new(Duration::from_millis(1000)));
+    info!("module start: {}", hb.name());
+    tasks.push(hb.spawn(ctx(&events_tx)));
+
+    // Scheduler @ 250ms tick (adaptively paced, see `SchedulerConfig`), concurrency = num_cpus
+    let still_failing_threshold = conf.notifier.as_ref().map_or(5, |n| n.still_failing_threshold);
+    let sch = Box::new(Scheduler::new(
+        250,
+        num_cpus::get(),
+        still_failing_threshold,
+        conf.scheduler.target_tranquility,
+        conf.scheduler.min_tick_ms,
+        conf.scheduler.max_tick_ms,
+    ));
     info!("module start: {}", sch.name());
-    tasks.push(sch.spawn(ModuleCtx {
-        kv: kv.clone(),
-        shutdown: shutdown_rx.clone(),
-    }));
+    tasks.push(sch.spawn(ctx(&events_tx)));
+
+    // Notifier: watches job-state transitions and alerts configured sinks
+    let ntf = Box::new(Notifier::new(conf.notifier.clone().unwrap_or_default()));
+    info!("module start: {}", ntf.name());
+    tasks.push(ntf.spawn(ctx(&events_tx)));
+
+    // Optional remote-agent mode: poll a control-plane server alongside the local scheduler
+    #[cfg(feature = "agent")]
+    if let Some(server_url) = cli.agent_server.clone() {
+        let agent_id = cli.agent_id.clone().unwrap_or_else(|| {
+            std::env::var("HOSTNAME").unwrap_or_else(|_| "agent".to_string())
+        });
+        let ag = Box::new(agent::Agent::new(server_url, agent_id, cli.agent_token.clone(), Duration::from_millis(1000)));
+        info!("module start: {}", ag.name());
+        tasks.push(ag.spawn(ctx(&events_tx)));
+    }
 
     // Optional HTTP API
     #[cfg(feature = "web-api")]
     {
         use std::net::SocketAddr;
         let http: SocketAddr = "127.0.0.1:8080".parse().unwrap();
-        let srv = Box::new(web::WebServer::new(Some(http), None, None, None));
+        let server = web::WebServer::new(Some(http), None, None, None);
+        #[cfg(feature = "agent")]
+        let server = server.with_agent_token(conf.agent.as_ref().and_then(|a| a.token.clone()));
+        #[cfg(feature = "agent")]
+        let server = {
+            let a = conf.agent.clone().unwrap_or_default();
+            server.with_agent_liveness(a.heartbeat_interval_ms, a.idle_after_missed, a.offline_after_missed)
+        };
+        let srv = Box::new(server);
         info!("module start: {}", srv.name());
-        tasks.push(srv.spawn(ModuleCtx { kv: kv.clone(), shutdown: shutdown_rx.clone() }));
+        tasks.push(srv.spawn(ctx(&events_tx)));
     }
 
     info!("runtime: modules started; press Ctrl+C to stop");
@@ -100,7 +157,7 @@ fn seed_demo_job(kv: &DefaultKv) -> Result<()> {
     if !ids.iter().any(|i| i == "demo") {
         ids.push("demo".to_string());
         kv.put_t(&ns("jobs", "registry"), &ids)?;
-        let spec = JobSpec { period_ms: 3000, action: Action::Noop };
+        let spec = JobSpec { schedule: Schedule::Every { period_ms: 3000, jitter_ms: 0 }, action: Action::Noop, depends_on: Vec::new(), trigger: ai_core::job::Trigger::default(), tolerate_nonzero_exit: false, assigned_to: None };
         kv.put_t(&ns("jobs", "demo:spec"), &spec)?;
         info!("seeded demo job: id=demo period=3000ms");
     }
@@ -1,10 +1,14 @@
+use ai_core::job::JobEvent;
 use ai_core::store::DefaultKv;
+use tokio::sync::broadcast;
 use tokio::task::JoinHandle;
 
 #[derive(Clone)]
 pub struct ModuleCtx {
     pub kv: DefaultKv,
     pub shutdown: tokio::sync::watch::Receiver<bool>,
+    /// Job state transitions (failing/recovered/still-failing), broadcast by the scheduler.
+    pub events: broadcast::Sender<JobEvent>,
 }
 
 pub trait Module: Send + 'static {
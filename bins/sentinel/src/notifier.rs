@@ -0,0 +1,83 @@
+use std::process::Stdio;
+
+use ai_core::cfg::{NotifierConfig, NotifierSink};
+use ai_core::job::JobEvent;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::module::{Module, ModuleCtx};
+
+/// Watches `JobEvent`s broadcast by the scheduler and delivers them to configured sinks.
+pub struct Notifier {
+    config: NotifierConfig,
+}
+
+impl Notifier {
+    pub fn new(config: NotifierConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Module for Notifier {
+    fn name(&self) -> &'static str { "notifier" }
+
+    fn spawn(self: Box<Self>, mut ctx: ModuleCtx) -> tokio::task::JoinHandle<anyhow::Result<()>> {
+        tokio::spawn(async move {
+            if self.config.sinks.is_empty() {
+                info!("notifier idle: no sinks configured");
+            }
+            let mut events = ctx.events.subscribe();
+
+            loop {
+                tokio::select! {
+                    event = events.recv() => {
+                        match event {
+                            Ok(ev) => {
+                                for sink in &self.config.sinks {
+                                    if let Err(e) = deliver(sink, &ev).await {
+                                        warn!("notifier sink failed id={} err={}", ev.id, e);
+                                    }
+                                }
+                            }
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                                warn!("notifier lagged, skipped {} events", skipped);
+                            }
+                            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                        }
+                    }
+                    changed = ctx.shutdown.changed() => {
+                        if changed.is_ok() && *ctx.shutdown.borrow() {
+                            info!("notifier stopping");
+                            break;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+async fn deliver(sink: &NotifierSink, event: &JobEvent) -> anyhow::Result<()> {
+    match sink {
+        NotifierSink::Webhook { url, method } => {
+            let client = reqwest::Client::new();
+            let verb = reqwest::Method::from_bytes(method.as_bytes()).unwrap_or(reqwest::Method::POST);
+            client.request(verb, url).json(event).send().await?.error_for_status()?;
+            Ok(())
+        }
+        NotifierSink::Exec { cmd, args } => {
+            let payload = serde_json::to_vec(event)?;
+            let mut child = Command::new(cmd)
+                .args(args)
+                .stdin(Stdio::piped())
+                .spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(&payload).await?;
+            }
+            child.wait().await?;
+            Ok(())
+        }
+    }
+}
@@ -1,13 +1,31 @@
 use anyhow::{bail, Context, Result};
-use ai_core::job::Action;
+use ai_core::job::{Action, ExecResult, JobError};
 use ai_core::store::{Kv, KvSerde};
+use std::process::Stdio;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tokio::process::Command;
 use tokio::time::{timeout, Duration};
 
-/// Execute one job action against the given KV.
-pub async fn execute<K: Kv + KvSerde>(action: &Action, kv: &K) -> Result<()> {
+/// Cap on captured stdout/stderr bytes so a runaway process can't blow up the KV.
+const OUTPUT_TRUNCATE_BYTES: usize = 64 * 1024;
+
+/// Execute one job action against the given KV, returning the captured [`ExecResult`].
+///
+/// A non-zero exit code is reported via `ExecResult::exit_code`, not as an `Err` —
+/// callers (the scheduler) decide success/failure from it. `Err` covers runs that
+/// produce no result (spawn error, timeout, Lua script error, bad `KvPut` value)
+/// and an `Http` response whose status is not the expected one.
+pub async fn execute<K: Kv + KvSerde>(action: &Action, kv: &K) -> Result<ExecResult> {
+    let start = Instant::now();
+    let mut result = run(action, kv).await?;
+    result.duration_ms = start.elapsed().as_millis() as u64;
+    result.finished_at_ms = now_ms();
+    Ok(result)
+}
+
+async fn run<K: Kv + KvSerde>(action: &Action, kv: &K) -> Result<ExecResult> {
     match action {
-        Action::Noop => Ok(()),
+        Action::Noop => Ok(ExecResult::empty()),
 
         Action::KvPut { key, decode, value } => {
             let kb = key.as_bytes();
@@ -17,7 +35,7 @@ pub async fn execute<K: Kv + KvSerde>(action: &Action, kv: &K) -> Result<()> {
                         .as_str()
                         .ok_or_else(|| anyhow::anyhow!("value must be string"))?;
                     kv.put(kb, s.as_bytes());
-                    Ok(())
+                    Ok(ExecResult::empty())
                 }
                 "string" => {
                     let s = value
@@ -25,7 +43,7 @@ pub async fn execute<K: Kv + KvSerde>(action: &Action, kv: &K) -> Result<()> {
                         .ok_or_else(|| anyhow::anyhow!("value must be string"))?
                         .to_string();
                     kv.put_t(kb, &s)?;
-                    Ok(())
+                    Ok(ExecResult::empty())
                 }
                 "u32" => {
                     let n = value
@@ -33,14 +51,14 @@ pub async fn execute<K: Kv + KvSerde>(action: &Action, kv: &K) -> Result<()> {
                         .ok_or_else(|| anyhow::anyhow!("value must be number"))?;
                     let n32: u32 = n.try_into().context("out of range")?;
                     kv.put_t(kb, &n32)?;
-                    Ok(())
+                    Ok(ExecResult::empty())
                 }
                 "u64" => {
                     let n = value
                         .as_u64()
                         .ok_or_else(|| anyhow::anyhow!("value must be number"))?;
                     kv.put_t(kb, &n)?;
-                    Ok(())
+                    Ok(ExecResult::empty())
                 }
                 other => bail!("unknown decode {}", other),
             }
@@ -48,7 +66,7 @@ pub async fn execute<K: Kv + KvSerde>(action: &Action, kv: &K) -> Result<()> {
 
         Action::KvDel { key } => {
             let _ = kv.delete(key.as_bytes());
-            Ok(())
+            Ok(ExecResult::empty())
         }
 
         Action::Exec { cmd, args, timeout_ms } => {
@@ -56,27 +74,193 @@ pub async fn execute<K: Kv + KvSerde>(action: &Action, kv: &K) -> Result<()> {
             if !args.is_empty() {
                 c.args(args);
             }
-            let fut = c.status();
-
-            if let Some(ms) = timeout_ms {
-                let status = timeout(Duration::from_millis(*ms), fut)
-                    .await
-                    .context("exec timeout")??;
-                if !status.success() {
-                    bail!("exec exit status {:?}", status.code());
+            c.stdout(Stdio::piped()).stderr(Stdio::piped());
+            let fut = c.output();
+
+            let output = if let Some(ms) = timeout_ms {
+                match timeout(Duration::from_millis(*ms), fut).await {
+                    Ok(inner) => inner.map_err(|e| JobError::Spawn(e.to_string()))?,
+                    Err(_) => return Err(JobError::Timeout.into()),
                 }
             } else {
-                let status = fut.await?;
-                if !status.success() {
-                    bail!("exec exit status {:?}", status.code());
+                fut.await.map_err(|e| JobError::Spawn(e.to_string()))?
+            };
+
+            Ok(ExecResult {
+                exit_code: output.status.code(),
+                stdout: truncate(&output.stdout),
+                stderr: truncate(&output.stderr),
+                duration_ms: 0,
+                finished_at_ms: 0,
+            })
+        }
+
+        Action::Http { url, method, headers, body, timeout_ms, expect_status, store_to } => {
+            let method = match method.as_deref().unwrap_or("GET").to_ascii_uppercase().as_str() {
+                "GET" => reqwest::Method::GET,
+                "POST" => reqwest::Method::POST,
+                "PUT" => reqwest::Method::PUT,
+                "DELETE" => reqwest::Method::DELETE,
+                other => bail!("unsupported http method '{other}'"),
+            };
+
+            let client = reqwest::Client::new();
+            let mut req = client.request(method, url.as_str());
+            for (k, v) in headers {
+                req = req.header(k.as_str(), v.as_str());
+            }
+            if let Some(b) = body {
+                req = req.json(b);
+            }
+            let fut = req.send();
+
+            let resp = if let Some(ms) = timeout_ms {
+                match timeout(Duration::from_millis(*ms), fut).await {
+                    Ok(inner) => inner.map_err(|e| JobError::Spawn(e.to_string()))?,
+                    Err(_) => return Err(JobError::Timeout.into()),
                 }
+            } else {
+                fut.await.map_err(|e| JobError::Spawn(e.to_string()))?
+            };
+
+            let status = resp.status().as_u16();
+            let ok = match expect_status {
+                Some(expected) => status == *expected,
+                None => resp.status().is_success(),
+            };
+            let bytes = resp.bytes().await.map_err(|e| JobError::Io(e.to_string()))?;
+
+            if let Some(key) = store_to {
+                kv.put(key.as_bytes(), &bytes);
             }
-            Ok(())
+
+            if !ok {
+                return Err(JobError::Http(status).into());
+            }
+
+            Ok(ExecResult {
+                exit_code: Some(0),
+                stdout: truncate(&bytes),
+                stderr: String::new(),
+                duration_ms: 0,
+                finished_at_ms: 0,
+            })
+        }
+
+        Action::Lua { script, timeout_ms } => {
+            let kvc = kv.clone();
+            let scriptc = script.clone();
+            let fut = tokio::task::spawn_blocking(move || run_lua(&scriptc, kvc));
+
+            let joined = match timeout_ms {
+                Some(ms) => match timeout(Duration::from_millis(*ms), fut).await {
+                    Ok(inner) => inner,
+                    Err(_) => return Err(JobError::Timeout.into()),
+                },
+                None => fut.await,
+            };
+            let output = joined
+                .map_err(|e| JobError::Spawn(e.to_string()))?
+                .map_err(|e| JobError::Script(e.to_string()))?;
+
+            Ok(ExecResult { exit_code: Some(0), stdout: output, stderr: String::new(), duration_ms: 0, finished_at_ms: 0 })
         }
+    }
+}
+
+/// Max Lua instructions before a script is aborted, independent of the wall-clock timeout.
+const LUA_MAX_INSTRUCTIONS: u64 = 10_000_000;
 
-        Action::Http { .. } => {
-            // Not implemented in this minimal local runtime
-            bail!("http action not implemented in runner");
+/// Run `script` in a sandboxed Lua VM, binding `kv_get`/`kv_put`/`kv_del`/`log` to `kv`.
+/// Runs synchronously; callers are expected to invoke this via `spawn_blocking`.
+fn run_lua<K: Kv + KvSerde>(script: &str, kv: K) -> Result<String> {
+    // Sandboxed stdlib: base/table/string/math only. No `io`/`os` (filesystem,
+    // process exec, env), no `package` (so `require` doesn't exist either).
+    // `dofile`/`loadfile`/`load` still ship in `BASE`, so strip those
+    // explicitly below rather than trust the stdlib selection alone.
+    let lua = mlua::Lua::new_with(
+        mlua::StdLib::BASE | mlua::StdLib::TABLE | mlua::StdLib::STRING | mlua::StdLib::MATH,
+        mlua::LuaOptions::new(),
+    )
+    .context("create sandboxed lua state")?;
+
+    let mut instructions: u64 = 0;
+    lua.set_hook(mlua::HookTriggers::new().every_nth_instruction(1000), move |_, _| {
+        instructions += 1000;
+        if instructions > LUA_MAX_INSTRUCTIONS {
+            Err(mlua::Error::RuntimeError("instruction limit exceeded".into()))
+        } else {
+            Ok(())
         }
+    })
+    .context("install lua instruction hook")?;
+
+    let globals = lua.globals();
+
+    let kv_get_h = kv.clone();
+    globals.set(
+        "kv_get",
+        lua.create_function(move |_, key: String| Ok(kv_get_h.get(key.as_bytes()).map(|v| String::from_utf8_lossy(&v).into_owned())))?,
+    )?;
+
+    let kv_put_h = kv.clone();
+    globals.set(
+        "kv_put",
+        lua.create_function(move |_, (key, value): (String, String)| {
+            kv_put_h.put(key.as_bytes(), value.as_bytes());
+            Ok(())
+        })?,
+    )?;
+
+    let kv_del_h = kv.clone();
+    globals.set("kv_del", lua.create_function(move |_, key: String| Ok(kv_del_h.delete(key.as_bytes())))?)?;
+
+    globals.set(
+        "log",
+        lua.create_function(|_, msg: String| {
+            tracing::info!("lua: {}", msg);
+            Ok(())
+        })?,
+    )?;
+
+    // `os`/`io`/`require`/`package` don't ship with the stdlib subset loaded
+    // above, but `dofile`/`loadfile`/`load` do (in `BASE`); nil all of them.
+    for name in ["os", "io", "dofile", "loadfile", "load", "require", "package"] {
+        globals.set(name, mlua::Value::Nil)?;
+    }
+
+    let result: mlua::Value = lua.load(script).eval().context("lua script error")?;
+    Ok(lua_value_to_string(result))
+}
+
+fn lua_value_to_string(v: mlua::Value) -> String {
+    match v {
+        mlua::Value::Nil => String::new(),
+        mlua::Value::Boolean(b) => b.to_string(),
+        mlua::Value::Integer(i) => i.to_string(),
+        mlua::Value::Number(n) => n.to_string(),
+        mlua::Value::String(s) => s.to_str().map(|s| s.to_string()).unwrap_or_default(),
+        _ => String::new(),
     }
 }
+
+fn truncate(bytes: &[u8]) -> String {
+    let bytes = if bytes.len() > OUTPUT_TRUNCATE_BYTES { &bytes[..OUTPUT_TRUNCATE_BYTES] } else { bytes };
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Best-effort recovery of the [`JobError`] behind an `execute` failure.
+///
+/// Most failure paths in this module construct a `JobError` explicitly, but a
+/// few (KV decode errors, `bail!`s) still flow through as a bare
+/// `anyhow::Error`; those fall back to `JobError::Io` rather than being lost.
+pub fn classify(err: &anyhow::Error) -> JobError {
+    err.downcast_ref::<JobError>().cloned().unwrap_or_else(|| JobError::Io(err.to_string()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -1,25 +1,80 @@
+use std::collections::VecDeque;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use tokio::sync::Semaphore;
-use tokio::time::interval;
+use tokio::time::sleep;
 use tracing::{info, warn};
 
-use ai_core::job::{Action, JobSpec, JobState, LegacySpec};
+use ai_core::job::{Action, ExecResult, JobError, JobEvent, JobEventKind, JobSpec, JobSpecV1, JobState, LegacySpec, RunHistory, Schedule, Trigger};
 use ai_core::store::{KvSerde, ns};
 use crate::module::{Module, ModuleCtx};
 use crate::runner::execute;
 
+/// Cap (ms) on the summed busy time of the recent tick samples the tranquilizer keeps.
+const TRANQUILITY_WINDOW_MS: u64 = 5_000;
+
 /// Cooperative scheduler running periodic jobs persisted in KV.
+///
+/// Tick pacing is adaptive (the "tranquilizer"): rather than a fixed
+/// `tick_ms` interval, each tick's dispatch busy time feeds a sliding window
+/// used to compute the next sleep, holding the process roughly
+/// `target_tranquility` idle instead of hammering a fixed clock.
 pub struct Scheduler {
     tick_ms: u64,
     max_concurrency: usize,
     max_backoff_ms: u64,
+    still_failing_threshold: u64,
+    target_tranquility: f64,
+    min_tick_ms: u64,
+    max_tick_ms: u64,
 }
 
 impl Scheduler {
-    pub fn new(tick_ms: u64, max_concurrency: usize) -> Self {
-        Self { tick_ms, max_concurrency, max_backoff_ms: 60_000 }
+    pub fn new(
+        tick_ms: u64,
+        max_concurrency: usize,
+        still_failing_threshold: u64,
+        target_tranquility: f64,
+        min_tick_ms: u64,
+        max_tick_ms: u64,
+    ) -> Self {
+        Self {
+            tick_ms,
+            max_concurrency,
+            max_backoff_ms: 60_000,
+            still_failing_threshold,
+            target_tranquility,
+            min_tick_ms,
+            max_tick_ms,
+        }
+    }
+
+    /// Computes the next tick's sleep duration ("tranquilizer"): push this
+    /// tick's `(elapsed_ms, jobs_dispatched)` sample, drop the oldest samples
+    /// until their summed busy time is within [`TRANQUILITY_WINDOW_MS`] (the
+    /// newest is always kept), then derive the sleep from the window's
+    /// average busy time so the scheduler stays roughly `target_tranquility`
+    /// idle. Returns the configured `tick_ms` when no sample left in the
+    /// window dispatched a job.
+    fn next_tick_delay(&self, window: &mut VecDeque<(u64, usize)>, elapsed_ms: u64, dispatched: usize) -> Duration {
+        window.push_back((elapsed_ms, dispatched));
+        let mut span: u64 = window.iter().map(|(e, _)| *e).sum();
+        while span > TRANQUILITY_WINDOW_MS && window.len() > 1 {
+            if let Some((e, _)) = window.pop_front() {
+                span -= e;
+            }
+        }
+
+        let total_dispatched: usize = window.iter().map(|(_, d)| *d).sum();
+        if total_dispatched == 0 {
+            return Duration::from_millis(self.tick_ms);
+        }
+
+        let avg_busy_ms = span as f64 / window.len() as f64;
+        let t = self.target_tranquility.clamp(0.0, 0.99);
+        let sleep_ms = (avg_busy_ms * t / (1.0 - t)).round() as u64;
+        Duration::from_millis(sleep_ms.clamp(self.min_tick_ms, self.max_tick_ms))
     }
 }
 
@@ -29,27 +84,43 @@ impl Module for Scheduler {
     fn spawn(self: Box<Self>, mut ctx: ModuleCtx) -> tokio::task::JoinHandle<anyhow::Result<()>> {
         tokio::spawn(async move {
             let sem = Arc::new(Semaphore::new(self.max_concurrency));
-            let mut tick = interval(Duration::from_millis(self.tick_ms));
             let backoff_cap = self.max_backoff_ms;
+            let still_failing_threshold = self.still_failing_threshold;
+            let mut window: VecDeque<(u64, usize)> = VecDeque::new();
+            let mut next_sleep = Duration::from_millis(self.tick_ms);
 
             loop {
                 tokio::select! {
-                    _ = tick.tick() => {
+                    _ = sleep(next_sleep) => {
+                        let tick_start = Instant::now();
                         let ids: Vec<String> = ctx.kv.get_t(&ns("jobs", "registry"))?.unwrap_or_default();
                         let now = now_ms();
+                        let mut dispatched = 0usize;
+
+                        // Dependency view: every job's current state, read once per tick so
+                        // `OnSuccessOf`/`OnCompletionOf` triggers can check a parent's latest
+                        // generation without an extra KV round-trip per child.
+                        let mut states: std::collections::HashMap<String, JobState> = std::collections::HashMap::new();
+                        for id in &ids {
+                            let st = ctx.kv.get_t::<JobState>(&ns("jobs", &format!("{id}:state"))).ok().flatten().unwrap_or_default();
+                            states.insert(id.clone(), st);
+                        }
 
                         for id in ids {
                             let spec_key = ns("jobs", &format!("{id}:spec"));
 
-                            // Try new -> legacy -> stored String(JSON) fallbacks
+                            // Try new -> v1 (period_ms) -> legacy -> stored String(JSON) fallbacks
                             let spec = ctx.kv.get_t::<JobSpec>(&spec_key).ok().flatten()
+                                .or_else(|| {
+                                    ctx.kv.get_t::<JobSpecV1>(&spec_key).ok().flatten().map(JobSpec::from)
+                                })
                                 .or_else(|| {
                                     if let Ok(Some(old)) = ctx.kv.get_t::<LegacySpec>(&spec_key) {
                                         let action = match old.cmd.as_str() {
                                             "noop" => Action::Noop,
                                             _ => { warn!("legacy cmd {} -> noop", old.cmd); Action::Noop }
                                         };
-                                        Some(JobSpec { period_ms: old.period_ms, action })
+                                        Some(JobSpec { schedule: Schedule::Every { period_ms: old.period_ms, jitter_ms: 0 }, action, depends_on: Vec::new(), trigger: ai_core::job::Trigger::default(), tolerate_nonzero_exit: false, assigned_to: None })
                                     } else { None }
                                 })
                                 .or_else(|| {
@@ -61,10 +132,45 @@ impl Module for Scheduler {
                             let Some(spec) = spec else { continue };
 
                             let state_key = ns("jobs", &format!("{id}:state"));
-                            let state = ctx.kv.get_t::<JobState>(&state_key)?.unwrap_or_default();
+                            let mut state = states.get(&id).cloned().unwrap_or_default();
+
+                            // For a dependency-triggered job, the parent generation this tick
+                            // would consume — not persisted until dispatch is actually
+                            // committed (permit acquired), so a saturated scheduler doesn't
+                            // lose the completion by marking it consumed anyway.
+                            let mut new_trigger_generation: Option<u64> = None;
 
-                            let effective_period = if state.backoff_ms > 0 { state.backoff_ms } else { spec.period_ms };
-                            let due = now.saturating_sub(state.last_run_ms) >= effective_period;
+                            let due = match spec.trigger.parent() {
+                                None => {
+                                    if state.backoff_ms > 0 {
+                                        now.saturating_sub(state.last_run_ms) >= state.backoff_ms
+                                    } else {
+                                        if state.next_run_ms == 0 {
+                                            state.next_run_ms = spec.schedule.next_fire_ms(&id, state.last_run_ms, now);
+                                            let _ = ctx.kv.put_t(&state_key, &state);
+                                        }
+                                        now >= state.next_run_ms
+                                    }
+                                }
+                                // Dependency-triggered jobs ignore the schedule/backoff clock
+                                // entirely: they fire at most once per parent generation, gated
+                                // on the parent's latest completion (and success, for `OnSuccessOf`).
+                                Some(parent_id) => {
+                                    match states.get(parent_id) {
+                                        Some(parent_state) => {
+                                            let fresh = parent_state.generation > state.last_trigger_generation;
+                                            let ok_gate = !matches!(spec.trigger, Trigger::OnSuccessOf(_)) || parent_state.last_ok;
+                                            if fresh && ok_gate {
+                                                new_trigger_generation = Some(parent_state.generation);
+                                                true
+                                            } else {
+                                                false
+                                            }
+                                        }
+                                        None => false,
+                                    }
+                                }
+                            };
                             if !due { continue; }
 
                             // Concurrency gate
@@ -72,35 +178,87 @@ impl Module for Scheduler {
                                 Ok(p) => p,
                                 Err(_) => { continue; } // saturated
                             };
+                            dispatched += 1;
+
+                            // Now that dispatch is committed, consume the parent generation.
+                            if let Some(gen) = new_trigger_generation {
+                                state.last_trigger_generation = gen;
+                                let _ = ctx.kv.put_t(&state_key, &state);
+                            }
 
                             let kvc = ctx.kv.clone();
                             let idc = id.clone();
                             let specc = spec.clone();
                             let state_keyc = state_key.clone();
+                            let eventsc = ctx.events.clone();
 
                             tokio::spawn(async move {
                                 let res = execute(&specc.action, &kvc).await;
 
                                 let mut st = kvc.get_t::<JobState>(&state_keyc).ok().flatten().unwrap_or_default();
+                                let prev_failures = st.failures;
                                 st.last_run_ms = now_ms();
+                                let mut last_error: Option<String> = None;
                                 match res {
-                                    Ok(_) => {
-                                        st.runs = st.runs.saturating_add(1);
-                                        st.failures = 0;
-                                        st.backoff_ms = 0;
+                                    Ok(exec) => {
+                                        let success = exec.exit_code.map_or(true, |c| c == 0);
+                                        st.generation = st.generation.saturating_add(1);
+                                        st.last_ok = success;
+                                        if success {
+                                            st.runs = st.runs.saturating_add(1);
+                                            st.failures = 0;
+                                            st.backoff_ms = 0;
+                                            st.last_error_kind = None;
+                                            st.last_error_msg = None;
+                                            st.next_run_ms = specc.schedule.next_fire_ms(&idc, st.last_run_ms, st.last_run_ms);
+                                            info!("job ok id={}", idc);
+                                        } else {
+                                            st.failures = st.failures.saturating_add(1);
+                                            let kind = JobError::NonZeroExit(exec.exit_code.unwrap_or(-1));
+                                            if specc.tolerate_nonzero_exit {
+                                                st.backoff_ms = 0;
+                                            } else {
+                                                st.backoff_ms = (st.backoff_ms.max(specc.schedule.backoff_base_ms()).saturating_mul(2)).min(backoff_cap);
+                                            }
+                                            st.last_error_kind = Some(kind.kind().to_string());
+                                            st.last_error_msg = Some(kind.to_string());
+                                            last_error = Some(format!("exit code {:?}", exec.exit_code));
+                                            warn!("job exit nonzero id={} code={:?}", idc, exec.exit_code);
+                                        }
                                         let _ = kvc.put_t(&state_keyc, &st);
-                                        info!("job ok id={}", idc);
+                                        record_history(&kvc, &idc, exec);
                                     }
                                     Err(e) => {
+                                        st.generation = st.generation.saturating_add(1);
+                                        st.last_ok = false;
                                         st.failures = st.failures.saturating_add(1);
-                                        st.backoff_ms = (st.backoff_ms.max(specc.period_ms).saturating_mul(2)).min(backoff_cap);
+                                        let kind = crate::runner::classify(&e);
+                                        // A spawn failure usually means a broken command/binary, not
+                                        // a transient blip — back off harder than other kinds.
+                                        let multiplier = if matches!(kind, JobError::Spawn(_)) { 4 } else { 2 };
+                                        st.backoff_ms = (st.backoff_ms.max(specc.schedule.backoff_base_ms()).saturating_mul(multiplier)).min(backoff_cap);
+                                        st.last_error_kind = Some(kind.kind().to_string());
+                                        st.last_error_msg = Some(kind.to_string());
+                                        last_error = Some(e.to_string());
                                         let _ = kvc.put_t(&state_keyc, &st);
                                         warn!("job err id={} err={}", idc, e);
+                                        record_history(&kvc, &idc, ExecResult {
+                                            exit_code: None,
+                                            stdout: String::new(),
+                                            stderr: e.to_string(),
+                                            duration_ms: 0,
+                                            finished_at_ms: st.last_run_ms,
+                                        });
                                     }
                                 }
+
+                                emit_transition(&eventsc, &idc, prev_failures, st.failures, still_failing_threshold, last_error);
                                 drop(permit);
                             });
                         }
+
+                        let elapsed_ms = tick_start.elapsed().as_millis() as u64;
+                        next_sleep = self.next_tick_delay(&mut window, elapsed_ms, dispatched);
                     }
                     changed = ctx.shutdown.changed() => {
                         if changed.is_ok() && *ctx.shutdown.borrow() {
@@ -115,6 +273,43 @@ impl Module for Scheduler {
     }
 }
 
+/// Broadcast a `JobEvent` for the transition between `prev_failures` and `failures`, if any.
+///
+/// Each transition fires at most once: `StartedFailing`/`Recovered` only on the
+/// edge crossing 0, and `StillFailing` only the tick `failures` first reaches
+/// the threshold (not on every subsequent failure).
+fn emit_transition(
+    events: &tokio::sync::broadcast::Sender<JobEvent>,
+    id: &str,
+    prev_failures: u64,
+    failures: u64,
+    still_failing_threshold: u64,
+    last_error: Option<String>,
+) {
+    let kind = if prev_failures > 0 && failures == 0 {
+        Some(JobEventKind::Recovered)
+    } else if prev_failures == 0 && failures == 1 {
+        Some(JobEventKind::StartedFailing)
+    } else if still_failing_threshold > 0 && failures == still_failing_threshold {
+        Some(JobEventKind::StillFailing)
+    } else {
+        None
+    };
+    if let Some(kind) = kind {
+        let _ = events.send(JobEvent { id: id.to_string(), kind, failures, last_error });
+    }
+}
+
+/// Append `result` to the job's run-history ring buffer and persist it as the
+/// latest `{id}:result`, dropping the oldest history entry once full.
+fn record_history<K: KvSerde>(kv: &K, id: &str, result: ExecResult) {
+    let _ = kv.put_t(&ns("jobs", &format!("{id}:result")), &result);
+    let key = ns("jobs", &format!("{id}:history"));
+    let mut hist: RunHistory = kv.get_t(&key).ok().flatten().unwrap_or_default();
+    hist.push(result);
+    let _ = kv.put_t(&key, &hist);
+}
+
 fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -2,6 +2,8 @@
 
 use std::net::SocketAddr;
 use std::time::Instant;
+#[cfg(feature = "agent")]
+use std::time::Duration;
 
 use axum::{
     extract::{Path, Query, State},
@@ -10,12 +12,19 @@ use axum::{
     routing::{delete, get},
     Json, Router,
 };
+#[cfg(feature = "agent")]
+use axum::{http::HeaderMap, routing::post};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tokio::task::JoinHandle;
+#[cfg(feature = "agent")]
+use tokio::time::interval;
 use tracing::info;
+#[cfg(feature = "agent")]
+use tracing::warn;
 
-use ai_core::job::{Action, JobSpec, JobState, LegacySpec};
+use ai_core::job::{find_cycle, Action, JobSpec, JobSpecV1, JobState, LegacySpec, RunHistory, Schedule};
+use ai_core::job::ExecResult;
 use ai_core::store::{Kv, KvSerde, DefaultKv, ns};
 use crate::module::{Module, ModuleCtx};
 
@@ -23,12 +32,26 @@ use crate::module::{Module, ModuleCtx};
 struct AppState {
     kv: DefaultKv,
     started: Instant,
+    /// Bearer token agents must present on `/agents/*`; `None` disables auth.
+    #[cfg(feature = "agent")]
+    agent_token: Option<String>,
 }
 
 #[derive(Serialize)]
 struct Status {
     heartbeat_count: u64,
     uptime_ms: u64,
+    failing_jobs: Vec<FailingJob>,
+}
+
+/// A job currently mid-failure, surfaced so `/status` doesn't require a
+/// separate `/jobs` call just to see what's broken and why.
+#[derive(Serialize)]
+struct FailingJob {
+    id: String,
+    failures: u64,
+    error_kind: Option<String>,
+    error_msg: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -41,7 +64,7 @@ struct KvPutBody {
 }
 
 #[derive(Serialize)]
-struct JobView { id: String, spec: JobSpec, state: JobState }
+struct JobView { id: String, spec: JobSpec, state: JobState, last_result: Option<ExecResult> }
 
 #[derive(Deserialize)]
 struct JobUpsertLegacy { id: String, cmd: String, period_ms: u64 }
@@ -58,11 +81,54 @@ pub struct WebServer {
     pub https_addr: Option<SocketAddr>,
     pub tls_cert_pem: Option<String>,
     pub tls_key_pem: Option<String>,
+    /// Bearer token agents must present on `/agents/*`; `None` disables auth.
+    #[cfg(feature = "agent")]
+    pub agent_token: Option<String>,
+    /// Expected interval between agent heartbeats (ms); drives the liveness sweep.
+    #[cfg(feature = "agent")]
+    pub agent_heartbeat_interval_ms: u64,
+    /// Missed heartbeats before an agent is marked `Idle`.
+    #[cfg(feature = "agent")]
+    pub agent_idle_after_missed: u32,
+    /// Missed heartbeats before an agent is marked `Offline`.
+    #[cfg(feature = "agent")]
+    pub agent_offline_after_missed: u32,
 }
 
 impl WebServer {
     pub fn new(http: Option<SocketAddr>, https: Option<SocketAddr>, cert: Option<String>, key: Option<String>) -> Self {
-        Self { http_addr: http, https_addr: https, tls_cert_pem: cert, tls_key_pem: key }
+        Self {
+            http_addr: http,
+            https_addr: https,
+            tls_cert_pem: cert,
+            tls_key_pem: key,
+            #[cfg(feature = "agent")]
+            agent_token: None,
+            #[cfg(feature = "agent")]
+            agent_heartbeat_interval_ms: 1000,
+            #[cfg(feature = "agent")]
+            agent_idle_after_missed: 1,
+            #[cfg(feature = "agent")]
+            agent_offline_after_missed: 3,
+        }
+    }
+
+    /// Set the bearer token required of remote agents (feature `agent`).
+    #[cfg(feature = "agent")]
+    pub fn with_agent_token(mut self, token: Option<String>) -> Self {
+        self.agent_token = token;
+        self
+    }
+
+    /// Configure the agent liveness sweep (feature `agent`): the expected
+    /// heartbeat interval and how many missed heartbeats move an agent to
+    /// `Idle` / `Offline`.
+    #[cfg(feature = "agent")]
+    pub fn with_agent_liveness(mut self, heartbeat_interval_ms: u64, idle_after_missed: u32, offline_after_missed: u32) -> Self {
+        self.agent_heartbeat_interval_ms = heartbeat_interval_ms;
+        self.agent_idle_after_missed = idle_after_missed;
+        self.agent_offline_after_missed = offline_after_missed;
+        self
     }
 }
 
@@ -71,16 +137,57 @@ impl Module for WebServer {
 
     fn spawn(self: Box<Self>, ctx: ModuleCtx) -> JoinHandle<anyhow::Result<()>> {
         tokio::spawn(async move {
-            let state = AppState { kv: ctx.kv.clone(), started: Instant::now() };
+            let state = AppState {
+                kv: ctx.kv.clone(),
+                started: Instant::now(),
+                #[cfg(feature = "agent")]
+                agent_token: self.agent_token.clone(),
+            };
             let app = Router::new()
                 .route("/status", get(status))
+                .route("/metrics", get(metrics))
                 .route("/kv/:key", get(kv_get).put(kv_put).delete(kv_del))
                 .route("/jobs", get(jobs_list).post(jobs_upsert))
                 .route("/jobs/:id", delete(jobs_delete))
-                .with_state(state);
+                .route("/jobs/:id/history", get(jobs_history))
+                .route("/jobs/:id/result", get(jobs_result));
+
+            #[cfg(feature = "agent")]
+            let app = app
+                .route("/agents", get(agents_list))
+                .route("/agents/:id/register", post(agents_register))
+                .route("/agents/:id/jobs", get(agents_jobs_list).post(agents_jobs_assign))
+                .route("/agents/:id/results", post(agents_results));
+
+            let app = app.with_state(state);
 
             let mut servers = Vec::<tokio::task::JoinHandle<anyhow::Result<()>>>::new();
 
+            #[cfg(feature = "agent")]
+            {
+                let kv = ctx.kv.clone();
+                let mut sd = ctx.shutdown.clone();
+                let heartbeat_interval_ms = self.agent_heartbeat_interval_ms;
+                let idle_after_missed = self.agent_idle_after_missed;
+                let offline_after_missed = self.agent_offline_after_missed;
+                servers.push(tokio::spawn(async move {
+                    let mut tick = interval(Duration::from_millis(heartbeat_interval_ms.max(1)));
+                    loop {
+                        tokio::select! {
+                            _ = tick.tick() => {
+                                sweep_agent_liveness(&kv, heartbeat_interval_ms, idle_after_missed, offline_after_missed);
+                            }
+                            changed = sd.changed() => {
+                                if changed.is_ok() && *sd.borrow() {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Ok(())
+                }));
+            }
+
             if let Some(addr) = self.http_addr {
                 info!("web http listening on http://{}", addr);
                 let app_clone = app.clone();
@@ -121,7 +228,67 @@ impl Module for WebServer {
 async fn status(State(state): State<AppState>) -> impl IntoResponse {
     let count = state.kv.get_t::<u64>(&ns("heartbeat", "count")).ok().flatten().unwrap_or(0);
     let uptime_ms = state.started.elapsed().as_millis() as u64;
-    Json(json!(Status { heartbeat_count: count, uptime_ms }))
+
+    let ids: Vec<String> = state.kv.get_t(&ns("jobs", "registry")).ok().flatten().unwrap_or_default();
+    let failing_jobs = ids
+        .into_iter()
+        .filter_map(|id| {
+            let st: JobState = state.kv.get_t(&ns("jobs", &format!("{id}:state"))).ok().flatten()?;
+            if st.failures == 0 {
+                return None;
+            }
+            Some(FailingJob { id, failures: st.failures, error_kind: st.last_error_kind, error_msg: st.last_error_msg })
+        })
+        .collect();
+
+    Json(json!(Status { heartbeat_count: count, uptime_ms, failing_jobs }))
+}
+
+/// Renders Prometheus text-format exposition for the scheduler: per-job run
+/// counters/gauges walked from `ns("jobs","registry")`, plus heartbeat ticks
+/// and process uptime.
+async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    use std::fmt::Write;
+
+    let ids: Vec<String> = state.kv.get_t(&ns("jobs", "registry")).ok().flatten().unwrap_or_default();
+    let states: Vec<(String, JobState)> = ids
+        .into_iter()
+        .map(|id| {
+            let st: JobState = state.kv.get_t(&ns("jobs", &format!("{id}:state"))).ok().flatten().unwrap_or_default();
+            (id, st)
+        })
+        .collect();
+
+    let mut out = String::with_capacity(256 + states.len() * 128);
+
+    out.push_str("# HELP sentinel_job_runs_total Successful runs per job.\n");
+    out.push_str("# TYPE sentinel_job_runs_total counter\n");
+    for (id, st) in &states {
+        let _ = writeln!(out, "sentinel_job_runs_total{{id=\"{id}\"}} {}", st.runs);
+    }
+
+    out.push_str("# HELP sentinel_job_failures_total Consecutive failures per job.\n");
+    out.push_str("# TYPE sentinel_job_failures_total counter\n");
+    for (id, st) in &states {
+        let _ = writeln!(out, "sentinel_job_failures_total{{id=\"{id}\"}} {}", st.failures);
+    }
+
+    out.push_str("# HELP sentinel_job_backoff_ms Current backoff per job, 0 when disabled.\n");
+    out.push_str("# TYPE sentinel_job_backoff_ms gauge\n");
+    for (id, st) in &states {
+        let _ = writeln!(out, "sentinel_job_backoff_ms{{id=\"{id}\"}} {}", st.backoff_ms);
+    }
+
+    out.push_str("# HELP sentinel_heartbeat_ticks_total Total heartbeat ticks since boot.\n");
+    out.push_str("# TYPE sentinel_heartbeat_ticks_total counter\n");
+    let ticks = state.kv.get_t::<u64>(&ns("heartbeat", "count")).ok().flatten().unwrap_or(0);
+    let _ = writeln!(out, "sentinel_heartbeat_ticks_total {ticks}");
+
+    out.push_str("# HELP sentinel_uptime_seconds Process uptime in seconds.\n");
+    out.push_str("# TYPE sentinel_uptime_seconds gauge\n");
+    let _ = writeln!(out, "sentinel_uptime_seconds {}", state.started.elapsed().as_secs_f64());
+
+    ([(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")], out)
 }
 
 async fn kv_get(Path(key): Path<String>, State(state): State<AppState>, Query(q): Query<KvGetQuery>) -> impl IntoResponse {
@@ -203,10 +370,11 @@ async fn jobs_list(State(state): State<AppState>) -> impl IntoResponse {
     for id in ids {
         let k = ns("jobs", &format!("{id}:spec"));
         let spec_opt = state.kv.get_t::<JobSpec>(&k).ok().flatten()
+            .or_else(|| state.kv.get_t::<JobSpecV1>(&k).ok().flatten().map(JobSpec::from))
             .or_else(|| {
                 if let Ok(Some(old)) = state.kv.get_t::<LegacySpec>(&k) {
                     let action = match old.cmd.as_str() { "noop" => Action::Noop, _ => Action::Noop };
-                    Some(JobSpec { period_ms: old.period_ms, action })
+                    Some(JobSpec { schedule: Schedule::Every { period_ms: old.period_ms, jitter_ms: 0 }, action, depends_on: Vec::new(), trigger: ai_core::job::Trigger::default(), tolerate_nonzero_exit: false, assigned_to: None })
                 } else { None }
             })
             .or_else(|| {
@@ -216,7 +384,8 @@ async fn jobs_list(State(state): State<AppState>) -> impl IntoResponse {
             });
         if let Some(spec) = spec_opt {
             let state_j = state.kv.get_t::<JobState>(&ns("jobs", &format!("{id}:state"))).ok().flatten().unwrap_or_default();
-            out.push(JobView { id, spec, state: state_j });
+            let last_result = state.kv.get_t::<ExecResult>(&ns("jobs", &format!("{id}:result"))).ok().flatten();
+            out.push(JobView { id, spec, state: state_j, last_result });
         }
     }
     Json(out)
@@ -228,10 +397,18 @@ async fn jobs_upsert(State(state): State<AppState>, Json(payload): Json<JobUpser
         JobUpsertEither::New(j)    => (j.id, None, Some(j.spec)),
     };
 
-    // update registry
-    let mut ids: Vec<String> = state.kv.get_t(&ns("jobs", "registry")).ok().flatten().unwrap_or_default();
-    if !ids.iter().any(|i| i == &id) { ids.push(id.clone()); }
-    let _ = state.kv.put_t(&ns("jobs", "registry"), &ids);
+    if let Some(spec) = &newspec {
+        if let Some(cycle) = job_cycle(&state.kv, &id, &spec.effective_depends_on()) {
+            return (StatusCode::BAD_REQUEST, Json(json!({"error": format!("dependency cycle: {}", cycle.join(" -> "))}))).into_response();
+        }
+    }
+
+    // update registry; CAS-looped so concurrent upserts can't lose each other's entries
+    let _ = state.kv.update_t::<Vec<String>>(&ns("jobs", "registry"), |cur| {
+        let mut ids = cur.unwrap_or_default();
+        if !ids.iter().any(|i| i == &id) { ids.push(id.clone()); }
+        ids
+    });
 
     // store spec
     if let Some(l) = legacy {
@@ -240,15 +417,302 @@ async fn jobs_upsert(State(state): State<AppState>, Json(payload): Json<JobUpser
     if let Some(s) = newspec {
         let _ = state.kv.put_t(&ns("jobs", &format!("{}:spec", id)), &s);
     }
+    // reset state to start fresh, matching `scanner::upsert_job`
+    let _ = state.kv.put_t(&ns("jobs", &format!("{}:state", id)), &JobState::default());
 
-    Json(json!({"ok": true}))
+    Json(json!({"ok": true})).into_response()
+}
+
+/// Builds the job-registry's dependency edge map (every job's
+/// [`JobSpec::effective_depends_on`]) and checks whether setting `id`'s edges
+/// to `new_depends_on` would introduce a cycle. Mirrors `scanner`'s
+/// `job_cycle` check so the HTTP upsert path can't create the same deadlock
+/// the CLI path guards against.
+fn job_cycle(kv: &DefaultKv, id: &str, new_depends_on: &[String]) -> Option<Vec<String>> {
+    let ids: Vec<String> = kv.get_t(&ns("jobs", "registry")).ok().flatten().unwrap_or_default();
+    let mut edges = std::collections::HashMap::new();
+    for existing_id in &ids {
+        if existing_id == id {
+            continue;
+        }
+        let spec: Option<JobSpec> = kv.get_t(&ns("jobs", &format!("{existing_id}:spec"))).ok().flatten();
+        edges.insert(existing_id.clone(), spec.map(|s| s.effective_depends_on()).unwrap_or_default());
+    }
+    find_cycle(&edges, id, new_depends_on)
+}
+
+async fn jobs_history(Path(id): Path<String>, State(state): State<AppState>) -> impl IntoResponse {
+    let hist: RunHistory = state.kv.get_t(&ns("jobs", &format!("{id}:history"))).ok().flatten().unwrap_or_default();
+    Json(hist.results)
+}
+
+/// Latest captured [`ExecResult`] for a job, or 404 if it hasn't run yet.
+async fn jobs_result(Path(id): Path<String>, State(state): State<AppState>) -> impl IntoResponse {
+    match state.kv.get_t::<ExecResult>(&ns("jobs", &format!("{id}:result"))).ok().flatten() {
+        Some(result) => Json(result).into_response(),
+        None => (StatusCode::NOT_FOUND, "no result yet").into_response(),
+    }
+}
+
+/// A job assigned to a remote agent: the job id it runs under plus its spec.
+#[cfg(feature = "agent")]
+#[derive(Serialize, Deserialize, Clone)]
+struct AgentJob {
+    id: String,
+    spec: JobSpec,
+}
+
+#[cfg(feature = "agent")]
+#[derive(Deserialize)]
+struct AgentResultBody {
+    job_id: String,
+    result: ExecResult,
+}
+
+/// What an agent reports about itself on `/agents/:id/register`.
+#[cfg(feature = "agent")]
+#[derive(Deserialize)]
+struct AgentRegisterBody {
+    hostname: String,
+    #[serde(default)]
+    capabilities: Vec<String>,
+}
+
+/// Persisted record of a registered agent, under `ns("agents", "{id}")`.
+#[cfg(feature = "agent")]
+#[derive(Serialize, Deserialize, Clone)]
+struct AgentRecord {
+    id: String,
+    hostname: String,
+    capabilities: Vec<String>,
+    registered_at_ms: u64,
+}
+
+/// Liveness state machine for a registered agent, driven by heartbeats
+/// (register + every `/agents/:id/jobs` poll) and aged by the background
+/// sweeper in [`WebServer::spawn`].
+#[cfg(feature = "agent")]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum AgentLiveness {
+    /// No liveness recorded yet. Registration moves an agent straight to
+    /// `Active`, so only an agent with no stored state reads as `New`.
+    New,
+    /// Heard from within the last heartbeat interval.
+    Active,
+    /// Missed `idle_after_missed` heartbeats.
+    Idle,
+    /// Missed `offline_after_missed` heartbeats.
+    Offline,
+}
+
+/// Persisted liveness for an agent, under `ns("agents", "{id}:state")`.
+#[cfg(feature = "agent")]
+#[derive(Serialize, Deserialize, Clone)]
+struct AgentLivenessState {
+    state: AgentLiveness,
+    last_seen_ms: u64,
+}
+
+#[cfg(feature = "agent")]
+impl Default for AgentLivenessState {
+    fn default() -> Self {
+        Self { state: AgentLiveness::New, last_seen_ms: 0 }
+    }
+}
+
+/// `GET /agents` view: a registered agent plus its computed liveness.
+#[cfg(feature = "agent")]
+#[derive(Serialize)]
+struct AgentView {
+    id: String,
+    hostname: String,
+    capabilities: Vec<String>,
+    registered_at_ms: u64,
+    state: AgentLiveness,
+    last_seen_ms: u64,
+}
+
+/// Marks `id` as having just heard from it: bumps `last_seen_ms` and moves it
+/// to `Active`, logging the transition if it wasn't already.
+#[cfg(feature = "agent")]
+fn touch_agent_liveness(kv: &DefaultKv, id: &str) {
+    let key = ns("agents", &format!("{id}:state"));
+    let mut st: AgentLivenessState = kv.get_t(&key).ok().flatten().unwrap_or_default();
+    if st.state != AgentLiveness::Active {
+        info!("agent liveness id={} {:?} -> Active", id, st.state);
+    }
+    st.state = AgentLiveness::Active;
+    st.last_seen_ms = now_ms();
+    let _ = kv.put_t(&key, &st);
+}
+
+/// Ages every registered agent's liveness: `Idle` after `idle_after_missed`
+/// missed heartbeats, `Offline` after `offline_after_missed`.
+#[cfg(feature = "agent")]
+fn sweep_agent_liveness(kv: &DefaultKv, heartbeat_interval_ms: u64, idle_after_missed: u32, offline_after_missed: u32) {
+    let ids: Vec<String> = kv.get_t(&ns("agents", "registry")).ok().flatten().unwrap_or_default();
+    let now = now_ms();
+    for id in ids {
+        let key = ns("agents", &format!("{id}:state"));
+        let Some(mut st) = kv.get_t::<AgentLivenessState>(&key).ok().flatten() else { continue };
+        if st.state == AgentLiveness::New {
+            continue;
+        }
+        let missed = now.saturating_sub(st.last_seen_ms) / heartbeat_interval_ms.max(1);
+        let next = if missed >= offline_after_missed as u64 {
+            AgentLiveness::Offline
+        } else if missed >= idle_after_missed as u64 {
+            AgentLiveness::Idle
+        } else {
+            AgentLiveness::Active
+        };
+        if next != st.state {
+            if next == AgentLiveness::Offline {
+                warn!("agent liveness id={} {:?} -> {:?}", id, st.state, next);
+            } else {
+                info!("agent liveness id={} {:?} -> {:?}", id, st.state, next);
+            }
+            st.state = next;
+            let _ = kv.put_t(&key, &st);
+        }
+    }
+}
+
+/// Body of `POST /agents/:id/jobs`: assign an existing job (by id) to this agent.
+#[cfg(feature = "agent")]
+#[derive(Deserialize)]
+struct AgentAssignBody {
+    job_id: String,
+}
+
+/// Checks the `Authorization: Bearer <token>` header against the configured
+/// agent token. A `None` token (no `[agent]` config) leaves the endpoints open.
+#[cfg(feature = "agent")]
+fn check_agent_token(state: &AppState, headers: &HeaderMap) -> Result<(), StatusCode> {
+    let Some(expected) = &state.agent_token else { return Ok(()) };
+    let got = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if got == Some(expected.as_str()) {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+#[cfg(feature = "agent")]
+async fn agents_register(Path(id): Path<String>, State(state): State<AppState>, headers: HeaderMap, Json(body): Json<AgentRegisterBody>) -> impl IntoResponse {
+    if let Err(code) = check_agent_token(&state, &headers) {
+        return code.into_response();
+    }
+    let record = AgentRecord {
+        id: id.clone(),
+        hostname: body.hostname,
+        capabilities: body.capabilities,
+        registered_at_ms: now_ms(),
+    };
+    let _ = state.kv.put_t(&ns("agents", &id), &record);
+
+    let _ = state.kv.update_t::<Vec<String>>(&ns("agents", "registry"), |cur| {
+        let mut ids = cur.unwrap_or_default();
+        if !ids.iter().any(|i| i == &id) { ids.push(id.clone()); }
+        ids
+    });
+
+    touch_agent_liveness(&state.kv, &id);
+    info!("agent registered id={} hostname={}", id, record.hostname);
+    Json(json!({"ok": true})).into_response()
+}
+
+/// All registered agents with their computed liveness.
+#[cfg(feature = "agent")]
+async fn agents_list(State(state): State<AppState>) -> impl IntoResponse {
+    let ids: Vec<String> = state.kv.get_t(&ns("agents", "registry")).ok().flatten().unwrap_or_default();
+    let out: Vec<AgentView> = ids
+        .into_iter()
+        .filter_map(|id| {
+            let record: AgentRecord = state.kv.get_t(&ns("agents", &id)).ok().flatten()?;
+            let live: AgentLivenessState = state.kv.get_t(&ns("agents", &format!("{id}:state"))).ok().flatten().unwrap_or_default();
+            Some(AgentView {
+                id: record.id,
+                hostname: record.hostname,
+                capabilities: record.capabilities,
+                registered_at_ms: record.registered_at_ms,
+                state: live.state,
+                last_seen_ms: live.last_seen_ms,
+            })
+        })
+        .collect();
+    Json(out)
+}
+
+/// Jobs assigned to `id`: every job in the registry whose spec's `assigned_to`
+/// is this agent, plus unassigned (`None`) jobs, which broadcast to any agent.
+#[cfg(feature = "agent")]
+async fn agents_jobs_list(Path(id): Path<String>, State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    if let Err(code) = check_agent_token(&state, &headers) {
+        return code.into_response();
+    }
+    touch_agent_liveness(&state.kv, &id);
+    let ids: Vec<String> = state.kv.get_t(&ns("jobs", "registry")).ok().flatten().unwrap_or_default();
+    let jobs: Vec<AgentJob> = ids
+        .into_iter()
+        .filter_map(|job_id| {
+            let spec: JobSpec = state.kv.get_t(&ns("jobs", &format!("{job_id}:spec"))).ok().flatten()?;
+            match &spec.assigned_to {
+                Some(target) if target == &id => Some(AgentJob { id: job_id, spec }),
+                None => Some(AgentJob { id: job_id, spec }),
+                Some(_) => None,
+            }
+        })
+        .collect();
+    Json(jobs).into_response()
+}
+
+/// Assigns an existing job (by id) to agent `id`, by setting its spec's `assigned_to`.
+#[cfg(feature = "agent")]
+async fn agents_jobs_assign(Path(id): Path<String>, State(state): State<AppState>, headers: HeaderMap, Json(body): Json<AgentAssignBody>) -> impl IntoResponse {
+    if let Err(code) = check_agent_token(&state, &headers) {
+        return code.into_response();
+    }
+    let spec_key = ns("jobs", &format!("{}:spec", body.job_id));
+    let Some(mut spec) = state.kv.get_t::<JobSpec>(&spec_key).ok().flatten() else {
+        return (StatusCode::NOT_FOUND, "no such job").into_response();
+    };
+    spec.assigned_to = Some(id);
+    let _ = state.kv.put_t(&spec_key, &spec);
+    Json(json!({"ok": true})).into_response()
+}
+
+#[cfg(feature = "agent")]
+async fn agents_results(Path(id): Path<String>, State(state): State<AppState>, headers: HeaderMap, Json(body): Json<AgentResultBody>) -> impl IntoResponse {
+    if let Err(code) = check_agent_token(&state, &headers) {
+        return code.into_response();
+    }
+    touch_agent_liveness(&state.kv, &id);
+    let _ = state.kv.put_t(&ns("jobs", &format!("{}:result", body.job_id)), &body.result);
+    let hist_key = ns("jobs", &format!("{}:history", body.job_id));
+    let mut hist: RunHistory = state.kv.get_t(&hist_key).ok().flatten().unwrap_or_default();
+    hist.push(body.result);
+    let _ = state.kv.put_t(&hist_key, &hist);
+    Json(json!({"ok": true})).into_response()
 }
 
 async fn jobs_delete(Path(id): Path<String>, State(state): State<AppState>) -> impl IntoResponse {
-    let mut ids: Vec<String> = state.kv.get_t(&ns("jobs", "registry")).ok().flatten().unwrap_or_default();
-    ids.retain(|i| i != &id);
-    let _ = state.kv.put_t(&ns("jobs", "registry"), &ids);
+    let _ = state.kv.update_t::<Vec<String>>(&ns("jobs", "registry"), |cur| {
+        let mut ids = cur.unwrap_or_default();
+        ids.retain(|i| i != &id);
+        ids
+    });
     let _ = state.kv.delete(&ns("jobs", &format!("{id}:spec")));
     let _ = state.kv.delete(&ns("jobs", &format!("{id}:state")));
     Json(json!({"ok": true}))
 }
+
+#[cfg(feature = "agent")]
+fn now_ms() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
@@ -33,7 +33,7 @@ fn main() -> Result<()> {
     if let Some(lv) = &cli.log {
         conf.log_level = lv.clone();
     }
-    logx::init(&conf.log_level);
+    let _log_guard = logx::init(&conf.log_level, conf.log_dir.as_deref(), &conf.log_rotation);
 
     let kv = open_kv()?;
 
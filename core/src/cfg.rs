@@ -20,12 +20,126 @@ pub struct AppId {
 pub struct Config {
     /// Tracing level to use if `RUST_LOG` is not set (e.g. `"info"`).
     pub log_level: String,
+    /// Directory for rotated log files; absent means stdout-only logging.
+    #[serde(default)]
+    pub log_dir: Option<String>,
+    /// Rotation for `log_dir`: `"daily"` | `"hourly"` | `"never"`.
+    #[serde(default = "default_log_rotation")]
+    pub log_rotation: String,
     /// Optional DB path (legacy compat; not used by FS-KV).
     #[serde(default = "default_db_path")]
     pub db_path: String,
+    /// Job-failure alerting; absent disables the notifier entirely.
+    #[serde(default)]
+    pub notifier: Option<NotifierConfig>,
+    /// Agent/server mode (feature `agent`); absent leaves the control-plane
+    /// endpoints unauthenticated.
+    #[serde(default)]
+    pub agent: Option<AgentConfig>,
+    /// Adaptive tick pacing for the `Scheduler`.
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
 }
 
 fn default_db_path() -> String { "mem.db".to_string() }
+fn default_log_rotation() -> String { "daily".to_string() }
+
+/// Configuration for the `Notifier` module: where to send job state transitions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotifierConfig {
+    /// Sinks to deliver `JobEvent`s to.
+    #[serde(default)]
+    pub sinks: Vec<NotifierSink>,
+    /// Consecutive failures after which a `StillFailing` alert fires (0 disables it).
+    #[serde(default = "default_still_failing_threshold")]
+    pub still_failing_threshold: u64,
+}
+
+fn default_still_failing_threshold() -> u64 { 5 }
+
+/// A destination for notifier alerts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotifierSink {
+    /// POST the event as JSON to `url`.
+    Webhook {
+        url: String,
+        #[serde(default = "default_webhook_method")]
+        method: String,
+    },
+    /// Run `cmd` with `args`, passing the event as JSON on stdin.
+    Exec {
+        cmd: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+fn default_webhook_method() -> String { "POST".to_string() }
+
+/// Control-plane config for the `agent` feature: the shared token remote
+/// agents must present to register, poll, and report results, plus the
+/// liveness sweep parameters behind `GET /agents`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentConfig {
+    /// Bearer token required on `/agents/*` endpoints. `None` disables auth.
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Expected interval between agent heartbeats (ms); drives the idle/offline sweep.
+    #[serde(default = "default_heartbeat_interval_ms")]
+    pub heartbeat_interval_ms: u64,
+    /// Missed heartbeats before an agent is marked `Idle`.
+    #[serde(default = "default_idle_after_missed")]
+    pub idle_after_missed: u32,
+    /// Missed heartbeats before an agent is marked `Offline`.
+    #[serde(default = "default_offline_after_missed")]
+    pub offline_after_missed: u32,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            token: None,
+            heartbeat_interval_ms: default_heartbeat_interval_ms(),
+            idle_after_missed: default_idle_after_missed(),
+            offline_after_missed: default_offline_after_missed(),
+        }
+    }
+}
+
+fn default_heartbeat_interval_ms() -> u64 { 1000 }
+fn default_idle_after_missed() -> u32 { 1 }
+fn default_offline_after_missed() -> u32 { 3 }
+
+/// Adaptive pacing ("tranquilizer") for the scheduler's tick loop: instead of
+/// a fixed tick interval, the next sleep is derived from recent busy time so
+/// the process stays roughly `target_tranquility` idle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    /// Target fraction of time the scheduler should spend idle, in `[0, 1)`.
+    #[serde(default = "default_target_tranquility")]
+    pub target_tranquility: f64,
+    /// Floor on the adaptive tick interval (ms).
+    #[serde(default = "default_min_tick_ms")]
+    pub min_tick_ms: u64,
+    /// Ceiling on the adaptive tick interval (ms).
+    #[serde(default = "default_max_tick_ms")]
+    pub max_tick_ms: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            target_tranquility: default_target_tranquility(),
+            min_tick_ms: default_min_tick_ms(),
+            max_tick_ms: default_max_tick_ms(),
+        }
+    }
+}
+
+fn default_target_tranquility() -> f64 { 0.9 }
+fn default_min_tick_ms() -> u64 { 50 }
+fn default_max_tick_ms() -> u64 { 2000 }
 
 /// Return the configuration directory for this app, creating it if needed.
 pub fn config_dir(app: &AppId) -> Result<PathBuf> {
@@ -47,7 +161,15 @@ pub fn load_or_init(app: &AppId) -> Result<Config> {
             .with_context(|| format!("parse {}", path.display()))?;
         Ok(cfg)
     } else {
-        let cfg = Config { log_level: "info".to_string(), db_path: default_db_path() };
+        let cfg = Config {
+            log_level: "info".to_string(),
+            log_dir: None,
+            log_rotation: default_log_rotation(),
+            db_path: default_db_path(),
+            notifier: None,
+            agent: None,
+            scheduler: SchedulerConfig::default(),
+        };
         save_config(&path, &cfg)?;
         Ok(cfg)
     }
@@ -1,14 +1,314 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
 
 /// A scheduled job specification.
 #[derive(Serialize, Deserialize, Clone)]
 pub struct JobSpec {
+    /// When the job fires.
+    #[serde(default)]
+    pub schedule: Schedule,
+    /// The action to perform when the job triggers.
+    pub action: Action,
+    /// Parent job ids this job depends on, for DAG cycle detection and the
+    /// `jobs graph` view. Does not itself gate firing; see [`Trigger`]. Use
+    /// [`JobSpec::effective_depends_on`], not this field directly, when
+    /// building the dependency graph: `trigger`'s parent is a real runtime
+    /// dependency too, whether or not it's listed here.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// What fires this job: its own schedule, or a parent's outcome.
+    #[serde(default)]
+    pub trigger: Trigger,
+    /// If true, a non-zero exit is treated as an expected flake rather than a
+    /// systemic failure: it's still recorded, but doesn't escalate
+    /// `JobState::backoff_ms`. Useful for probes/health-checks that are
+    /// allowed to fail occasionally.
+    #[serde(default)]
+    pub tolerate_nonzero_exit: bool,
+    /// Remote agent id this job is pinned to, for distributed execution.
+    /// `None` broadcasts the job to any registered agent.
+    #[serde(default)]
+    pub assigned_to: Option<String>,
+}
+
+impl JobSpec {
+    /// The full set of dependency edges this spec implies at runtime: its
+    /// explicit `depends_on` plus `trigger`'s parent (if any), deduplicated.
+    /// Cycle detection and the `jobs graph` view must use this, not
+    /// `depends_on` alone, or a trigger-only dependency (no matching
+    /// `depends_on` entry) goes invisible to both.
+    pub fn effective_depends_on(&self) -> Vec<String> {
+        let mut deps = self.depends_on.clone();
+        if let Some(parent) = self.trigger.parent() {
+            if !deps.iter().any(|d| d == parent) {
+                deps.push(parent.to_string());
+            }
+        }
+        deps
+    }
+}
+
+/// What makes a job due to run.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Trigger {
+    /// Fires per `JobSpec::schedule`, as usual.
+    #[default]
+    OnSchedule,
+    /// Fires once per generation after `parent`'s latest run succeeds.
+    OnSuccessOf(String),
+    /// Fires once per generation after `parent`'s latest run completes
+    /// (success or failure).
+    OnCompletionOf(String),
+}
+
+impl Trigger {
+    /// The parent job id this trigger gates on, if any.
+    pub fn parent(&self) -> Option<&str> {
+        match self {
+            Trigger::OnSchedule => None,
+            Trigger::OnSuccessOf(parent) | Trigger::OnCompletionOf(parent) => Some(parent),
+        }
+    }
+}
+
+/// Walks the DAG formed by `edges` (every job id's current
+/// [`JobSpec::effective_depends_on`]), as it would be once `id`'s edges are
+/// set to `new_depends_on`, looking for a path back to `id`. Returns the
+/// cycle (`id -> ... -> id`) if one would be introduced.
+///
+/// `edges` should not contain an entry for `id` itself; callers build it
+/// from every *other* job in the registry.
+pub fn find_cycle(edges: &HashMap<String, Vec<String>>, id: &str, new_depends_on: &[String]) -> Option<Vec<String>> {
+    let mut edges = edges.clone();
+    edges.insert(id.to_string(), new_depends_on.to_vec());
+
+    let mut path = vec![id.to_string()];
+    walk_for_cycle(id, id, &edges, &mut path)
+}
+
+fn walk_for_cycle(node: &str, start: &str, edges: &HashMap<String, Vec<String>>, path: &mut Vec<String>) -> Option<Vec<String>> {
+    for parent in edges.get(node).map(Vec::as_slice).unwrap_or(&[]) {
+        if parent == start {
+            let mut cycle = path.clone();
+            cycle.push(parent.clone());
+            return Some(cycle);
+        }
+        if path.contains(parent) {
+            continue; // already on this path without reaching `start`: not the cycle we're looking for
+        }
+        path.push(parent.clone());
+        if let Some(cycle) = walk_for_cycle(parent, start, edges, path) {
+            return Some(cycle);
+        }
+        path.pop();
+    }
+    None
+}
+
+/// Pre-`Schedule` job spec shape (fixed `period_ms`), kept for backward-compat deserialization.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct JobSpecV1 {
     /// Period between runs (milliseconds).
     pub period_ms: u64,
     /// The action to perform when the job triggers.
     pub action: Action,
 }
 
+impl From<JobSpecV1> for JobSpec {
+    fn from(v1: JobSpecV1) -> Self {
+        JobSpec {
+            schedule: Schedule::Every { period_ms: v1.period_ms, jitter_ms: 0 },
+            action: v1.action,
+            depends_on: Vec::new(),
+            trigger: Trigger::default(),
+            tolerate_nonzero_exit: false,
+            assigned_to: None,
+        }
+    }
+}
+
+/// How a job's next run time is computed.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Schedule {
+    /// Fire every `period_ms`, offset by a per-job jitter to de-synchronize ticks.
+    Every {
+        /// Nominal period between runs (milliseconds).
+        period_ms: u64,
+        /// Extra per-job offset added to `period_ms`, stable across restarts.
+        #[serde(default)]
+        jitter_ms: u64,
+    },
+    /// Standard 5-field cron expression: `minute hour day-of-month month day-of-week`, in UTC.
+    Cron(String),
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::Every { period_ms: 1000, jitter_ms: 0 }
+    }
+}
+
+impl Schedule {
+    /// Compute the next fire time (ms since epoch) strictly after `last_run_ms`.
+    ///
+    /// Missed fires are never bursted: this always returns the single next due
+    /// instant, so a job that was offline through several cron ticks fires once.
+    pub fn next_fire_ms(&self, job_id: &str, last_run_ms: u64, now_ms: u64) -> u64 {
+        match self {
+            Schedule::Every { period_ms, jitter_ms } => {
+                let effective = period_ms.saturating_add(job_jitter_offset(job_id, *jitter_ms)).max(1);
+                last_run_ms.saturating_add(effective)
+            }
+            Schedule::Cron(expr) => {
+                let from = if last_run_ms == 0 { now_ms } else { last_run_ms };
+                match CronSchedule::parse(expr) {
+                    Ok(cs) => cs.next_after_ms(from),
+                    Err(_) => now_ms.saturating_add(60_000), // invalid expression: retry in a minute
+                }
+            }
+        }
+    }
+
+    /// Base period used to seed failure backoff. `Cron` schedules have no fixed
+    /// period, so a sane default is used instead.
+    pub fn backoff_base_ms(&self) -> u64 {
+        match self {
+            Schedule::Every { period_ms, .. } => *period_ms,
+            Schedule::Cron(_) => 60_000,
+        }
+    }
+}
+
+/// Stable per-job pseudo-random offset in `[0, jitter_ms)`, seeded from the job id
+/// (FNV-1a) so it doesn't change across restarts.
+fn job_jitter_offset(job_id: &str, jitter_ms: u64) -> u64 {
+    if jitter_ms == 0 {
+        return 0;
+    }
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in job_id.as_bytes() {
+        hash ^= u64::from(*b);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01B3);
+    }
+    hash % jitter_ms
+}
+
+/// Parsed 5-field cron expression: minute hour day-of-month month day-of-week.
+struct CronSchedule {
+    minutes: Vec<u8>,
+    hours: Vec<u8>,
+    doms: Vec<u8>,
+    months: Vec<u8>,
+    dows: Vec<u8>,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+impl CronSchedule {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!("cron expression must have 5 fields, got {}", fields.len()));
+        }
+        Ok(Self {
+            dom_restricted: fields[2] != "*",
+            dow_restricted: fields[4] != "*",
+            minutes: parse_cron_field(fields[0], 0, 59)?,
+            hours: parse_cron_field(fields[1], 0, 23)?,
+            doms: parse_cron_field(fields[2], 1, 31)?,
+            months: parse_cron_field(fields[3], 1, 12)?,
+            dows: parse_cron_field(fields[4], 0, 6)?,
+        })
+    }
+
+    fn matches(&self, dom: u32, month: u32, hour: u32, minute: u32, dow: u32) -> bool {
+        if !self.minutes.contains(&(minute as u8)) { return false; }
+        if !self.hours.contains(&(hour as u8)) { return false; }
+        if !self.months.contains(&(month as u8)) { return false; }
+        let dom_ok = self.doms.contains(&(dom as u8));
+        let dow_ok = self.dows.contains(&(dow as u8));
+        // Standard cron semantics: when both day-of-month and day-of-week are
+        // restricted, either matching is enough (an OR, not an AND).
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom_ok || dow_ok,
+            (true, false) => dom_ok,
+            (false, true) => dow_ok,
+            (false, false) => true,
+        }
+    }
+
+    /// Earliest minute-aligned timestamp strictly after `from_ms`, searched up to ~4 years out.
+    fn next_after_ms(&self, from_ms: u64) -> u64 {
+        let start_minute = from_ms / 60_000 + 1;
+        let limit_minutes = start_minute + 4 * 366 * 24 * 60;
+        let mut minute = start_minute;
+        while minute < limit_minutes {
+            let day = minute / (24 * 60);
+            let min_of_day = minute % (24 * 60);
+            let (_, month, dom) = civil_from_days(day as i64);
+            let dow = ((day + 4) % 7) as u32; // day 0 (1970-01-01) was a Thursday
+            let hour = (min_of_day / 60) as u32;
+            let min = (min_of_day % 60) as u32;
+            if self.matches(dom, month, hour, min, dow) {
+                return minute * 60_000;
+            }
+            minute += 1;
+        }
+        from_ms.saturating_add(60_000) // no match in window; fall back and retry
+    }
+}
+
+fn parse_cron_field(spec: &str, min: u8, max: u8) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for part in spec.split(',') {
+        let (range_part, step) = match part.split_once('/') {
+            Some((r, s)) => (r, s.parse::<u8>().map_err(|_| format!("bad step in '{part}'"))?.max(1)),
+            None => (part, 1u8),
+        };
+        let (lo, hi) = if range_part == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range_part.split_once('-') {
+            (
+                a.parse::<u8>().map_err(|_| format!("bad range in '{part}'"))?,
+                b.parse::<u8>().map_err(|_| format!("bad range in '{part}'"))?,
+            )
+        } else {
+            let v = range_part.parse::<u8>().map_err(|_| format!("bad value in '{part}'"))?;
+            (v, v)
+        };
+        let mut v = lo;
+        while v <= hi {
+            if v >= min && v <= max {
+                out.push(v);
+            }
+            v += step;
+        }
+    }
+    out.sort_unstable();
+    out.dedup();
+    if out.is_empty() {
+        return Err(format!("field '{spec}' matched nothing"));
+    }
+    Ok(out)
+}
+
+/// Howard Hinnant's `civil_from_days`: days since 1970-01-01 (UTC) -> (year, month, day).
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = (z - era * 146_097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (if m <= 2 { y + 1 } else { y }, m, d)
+}
+
 /// Actions that a job can perform.
 #[derive(Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -30,15 +330,24 @@ pub enum Action {
     Http {
         /// URL.
         url: String,
-        /// Method (GET by default).
+        /// Method (GET by default). One of GET/POST/PUT/DELETE.
         #[serde(default)]
         method: Option<String>,
-        /// Body (optional).
+        /// Extra request headers.
+        #[serde(default)]
+        headers: Vec<(String, String)>,
+        /// JSON request body (optional).
         #[serde(default)]
-        body: Option<String>,
+        body: Option<serde_json::Value>,
         /// Optional timeout (ms).
         #[serde(default)]
         timeout_ms: Option<u64>,
+        /// Response status required for the run to be a success; defaults to any 2xx.
+        #[serde(default)]
+        expect_status: Option<u16>,
+        /// KV key to write the response body bytes into, if set.
+        #[serde(default)]
+        store_to: Option<String>,
     },
     /// Write a value into KV.
     KvPut {
@@ -54,6 +363,17 @@ pub enum Action {
         /// Key to delete.
         key: String,
     },
+    /// Run a sandboxed Lua script against the KV.
+    Lua {
+        /// Script source. Exposes `kv_get(key)`, `kv_put(key, value)`, `kv_del(key)`
+        /// and `log(msg)` bound to the job's KV and tracing.
+        script: String,
+        /// Optional wall-clock timeout (ms). Separately, the runner aborts any
+        /// script after a fixed instruction budget, so a busy-looping script
+        /// can't hang the runner.
+        #[serde(default)]
+        timeout_ms: Option<u64>,
+    },
 }
 
 /// Legacy job spec kept for compatibility (cmd + period).
@@ -76,4 +396,228 @@ pub struct JobState {
     pub failures: u64,
     /// Current backoff (ms), 0 when disabled.
     pub backoff_ms: u64,
+    /// Next scheduled fire time (ms since epoch), persisted so restarts don't
+    /// recompute a different decision mid-period. 0 means "not yet computed".
+    #[serde(default)]
+    pub next_run_ms: u64,
+    /// Monotonic count of completed runs (success or failure). Lets dependent
+    /// jobs detect "a new completion happened" without racing on timestamps.
+    #[serde(default)]
+    pub generation: u64,
+    /// Whether the most recent completed run succeeded.
+    #[serde(default)]
+    pub last_ok: bool,
+    /// The parent's `generation` last consumed by this job's [`Trigger`], so a
+    /// child fires at most once per parent completion.
+    #[serde(default)]
+    pub last_trigger_generation: u64,
+    /// Machine-readable kind of the most recent failure (see [`JobError::kind`]).
+    /// Cleared back to `None` on the next successful run.
+    #[serde(default)]
+    pub last_error_kind: Option<String>,
+    /// Human-readable message for the most recent failure.
+    /// Cleared back to `None` on the next successful run.
+    #[serde(default)]
+    pub last_error_msg: Option<String>,
+}
+
+/// Typed classification of a job run failure.
+///
+/// `runner::execute` wraps these in `anyhow::Error` rather than changing its
+/// return type, since most of its error paths already flow through `?` via
+/// `anyhow::Context`; callers that care about the kind (the scheduler)
+/// recover it with `runner::classify`. This is what lets the scheduler tell
+/// a timeout apart from a spawn failure apart from a non-zero exit, instead
+/// of only ever seeing an opaque string.
+#[derive(Error, Debug, Clone)]
+pub enum JobError {
+    /// The action did not complete within its configured timeout.
+    #[error("timed out")]
+    Timeout,
+    /// The action could not even be started (e.g. `exec` of a missing binary).
+    #[error("failed to spawn: {0}")]
+    Spawn(String),
+    /// The action ran to completion with a non-zero exit code.
+    #[error("exited with non-zero code {0}")]
+    NonZeroExit(i32),
+    /// An I/O error unrelated to spawning (e.g. a KV read/write failure).
+    #[error("io error: {0}")]
+    Io(String),
+    /// An HTTP action's response status was not the expected one
+    /// (`expect_status` if set, otherwise any 2xx).
+    #[error("http error: status {0}")]
+    Http(u16),
+    /// A scripted (`Action::Lua`) action raised an error.
+    #[error("script error: {0}")]
+    Script(String),
+}
+
+impl JobError {
+    /// Short, stable, machine-readable kind, persisted as `JobState::last_error_kind`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            JobError::Timeout => "timeout",
+            JobError::Spawn(_) => "spawn",
+            JobError::NonZeroExit(_) => "non_zero_exit",
+            JobError::Io(_) => "io",
+            JobError::Http(_) => "http",
+            JobError::Script(_) => "script",
+        }
+    }
+}
+
+/// Captured outcome of a single job execution.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExecResult {
+    /// Process exit code for `Exec`; `Some(0)` after a successful `Http` or
+    /// `Lua` run; `None` for other actions and for runs with no exit status.
+    pub exit_code: Option<i32>,
+    /// Captured stdout (truncated; see `runner::OUTPUT_TRUNCATE_BYTES`).
+    pub stdout: String,
+    /// Captured stderr (truncated; see `runner::OUTPUT_TRUNCATE_BYTES`).
+    pub stderr: String,
+    /// Wall-clock duration of the run (milliseconds).
+    pub duration_ms: u64,
+    /// Timestamp (ms since epoch) when the run finished.
+    pub finished_at_ms: u64,
+}
+
+impl ExecResult {
+    /// An empty result for actions that don't produce process output (e.g. `KvPut`).
+    pub fn empty() -> Self {
+        Self { exit_code: None, stdout: String::new(), stderr: String::new(), duration_ms: 0, finished_at_ms: 0 }
+    }
+}
+
+/// A `JobState.failures` transition worth alerting on.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobEventKind {
+    /// `failures` crossed from 0 to 1.
+    StartedFailing,
+    /// `failures` dropped back to 0 after a run succeeded.
+    Recovered,
+    /// `failures` reached the configured "still failing" threshold.
+    StillFailing,
+}
+
+/// A job state transition, broadcast from the scheduler to the notifier.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JobEvent {
+    /// Job id the transition belongs to.
+    pub id: String,
+    /// Which transition fired.
+    pub kind: JobEventKind,
+    /// Consecutive failure count at the time of the transition.
+    pub failures: u64,
+    /// Most recent error message, if any.
+    pub last_error: Option<String>,
+}
+
+/// Bounded ring buffer of recent [`ExecResult`]s for a job, oldest first.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RunHistory {
+    pub results: Vec<ExecResult>,
+}
+
+impl RunHistory {
+    /// Max results retained per job; oldest is dropped once exceeded.
+    pub const CAPACITY: usize = 20;
+
+    /// Push a new result, dropping the oldest entry if at capacity.
+    pub fn push(&mut self, result: ExecResult) {
+        if self.results.len() >= Self::CAPACITY {
+            self.results.remove(0);
+        }
+        self.results.push(result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_from_days_matches_known_dates() {
+        assert_eq!(civil_from_days(19_783), (2024, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29)); // leap day
+        assert_eq!(civil_from_days(18_992), (2021, 12, 31));
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+    }
+
+    #[test]
+    fn parse_cron_field_expands_steps_and_ranges() {
+        assert_eq!(parse_cron_field("*/15", 0, 59).unwrap(), vec![0, 15, 30, 45]);
+        assert_eq!(parse_cron_field("1-3,7", 0, 10).unwrap(), vec![1, 2, 3, 7]);
+        assert_eq!(parse_cron_field("*", 0, 3).unwrap(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn parse_cron_field_rejects_out_of_range_values() {
+        assert!(parse_cron_field("70", 0, 59).is_err());
+        assert!(parse_cron_field("not-a-number", 0, 59).is_err());
+    }
+
+    #[test]
+    fn cron_schedule_rejects_malformed_expressions() {
+        assert!(CronSchedule::parse("0 0 * *").is_err()); // only 4 fields
+        assert!(CronSchedule::parse("0 0 * * *").is_ok());
+    }
+
+    #[test]
+    fn cron_schedule_next_after_ms_finds_next_daily_midnight() {
+        let cs = CronSchedule::parse("0 0 * * *").unwrap();
+        // 2024-03-01 12:00:00 UTC
+        let from_ms = 19_783 * 24 * 60 * 60_000 + 12 * 60 * 60_000;
+        let next = cs.next_after_ms(from_ms);
+        // Should land exactly on 2024-03-02 00:00:00 UTC.
+        assert_eq!(next, (19_783 + 1) * 24 * 60 * 60_000);
+    }
+
+    #[test]
+    fn cron_schedule_dom_dow_restricted_is_an_or() {
+        // Fires on the 1st of the month OR on Mondays (dow=1).
+        let cs = CronSchedule::parse("0 0 1 * 1").unwrap();
+        assert!(cs.matches(1, 6, 0, 0, 3)); // 1st of month, any weekday
+        assert!(cs.matches(15, 6, 0, 0, 1)); // a Monday, any day-of-month
+        assert!(!cs.matches(15, 6, 0, 0, 3)); // neither condition holds
+    }
+
+    #[test]
+    fn find_cycle_detects_direct_and_indirect_cycles() {
+        let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+        edges.insert("b".into(), vec!["a".into()]);
+        // "b" already depends on "a"; making "a" depend on "b" closes the
+        // 2-cycle a -> b -> a.
+        assert!(find_cycle(&edges, "a", &["b".to_string()]).is_some());
+
+        edges.clear();
+        // c -> b -> a; making "a" depend on "c" closes a -> c -> b -> a.
+        edges.insert("b".into(), vec!["a".into()]);
+        edges.insert("c".into(), vec!["b".into()]);
+        let cycle = find_cycle(&edges, "a", &["c".to_string()]);
+        assert!(cycle.is_some());
+    }
+
+    #[test]
+    fn find_cycle_allows_acyclic_dags() {
+        let mut edges: HashMap<String, Vec<String>> = HashMap::new();
+        edges.insert("b".into(), vec!["a".into()]);
+        edges.insert("c".into(), vec!["a".into()]);
+        // "d" depending on both "b" and "c" is a diamond, not a cycle.
+        assert!(find_cycle(&edges, "d", &["b".to_string(), "c".to_string()]).is_none());
+    }
+
+    #[test]
+    fn effective_depends_on_includes_trigger_parent() {
+        let spec = JobSpec {
+            schedule: Schedule::default(),
+            action: Action::Noop,
+            depends_on: vec![],
+            trigger: Trigger::OnSuccessOf("upstream".to_string()),
+            tolerate_nonzero_exit: false,
+            assigned_to: None,
+        };
+        assert_eq!(spec.effective_depends_on(), vec!["upstream".to_string()]);
+    }
 }
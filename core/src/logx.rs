@@ -1,12 +1,40 @@
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 /// Initialize `tracing` once. Respects `RUST_LOG`; falls back to `default_level`.
-pub fn init(default_level: &str) {
+///
+/// Always layers a stdout writer. If `log_dir` is `Some`, also layers a
+/// rotated file writer (`log_rotation`: `"daily"` | `"hourly"` | `"never"`,
+/// defaulting to daily on an unrecognized value) under that directory via a
+/// non-blocking writer. The returned [`WorkerGuard`] flushes the file writer
+/// on drop, so the caller must hold it for the process lifetime (e.g. bind it
+/// to a `let _guard = ...` in `main`) rather than let it drop immediately.
+pub fn init(default_level: &str, log_dir: Option<&str>, log_rotation: &str) -> Option<WorkerGuard> {
     if std::env::var_os("RUST_LOG").is_none() {
         std::env::set_var("RUST_LOG", default_level);
     }
-    let _ = fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_target(true)
+
+    let stdout_layer = fmt::layer().with_target(true);
+
+    let (file_layer, guard) = match log_dir {
+        Some(dir) => {
+            let rolling = match log_rotation {
+                "hourly" => tracing_appender::rolling::hourly(dir, "sentinel.log"),
+                "never" => tracing_appender::rolling::never(dir, "sentinel.log"),
+                _ => tracing_appender::rolling::daily(dir, "sentinel.log"),
+            };
+            let (non_blocking, guard) = tracing_appender::non_blocking(rolling);
+            let layer = fmt::layer().with_target(true).with_ansi(false).with_writer(non_blocking);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    let _ = tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(stdout_layer)
+        .with(file_layer)
         .try_init();
+
+    guard
 }
@@ -19,6 +19,23 @@ pub trait Kv: Clone + Send + Sync + 'static {
     fn put(&self, key: &[u8], val: &[u8]);
     /// Delete `key`; returns `true` if a value existed.
     fn delete(&self, key: &[u8]) -> bool;
+
+    /// Atomically set `key` to `new`, but only if its current value equals
+    /// `expected` (`None` meaning "key absent"). Returns whether the swap
+    /// applied. Callers needing a safe read-modify-write should loop this
+    /// (see [`KvSerde::update_t`]) rather than doing `get` then `put`.
+    ///
+    /// The default implementation is NOT atomic (a `get`-then-`put` with a
+    /// race window between them); implementations backed by a real store
+    /// should override it. [`FsKv`] does, via an exclusive lock file.
+    fn compare_and_swap(&self, key: &[u8], expected: Option<&[u8]>, new: &[u8]) -> bool {
+        let current = self.get(key);
+        if current.as_deref() != expected {
+            return false;
+        }
+        self.put(key, new);
+        true
+    }
 }
 
 /// Serde helpers layered on top of any [`Kv`] implementation.
@@ -40,6 +57,27 @@ pub trait KvSerde: Kv {
         self.put(key, &buf);
         Ok(())
     }
+
+    /// Safely read-modify-write `key`: loops `compare_and_swap` until `f`'s
+    /// result wins the race, so concurrent updaters never clobber each
+    /// other's writes. `f` receives the current typed value (`None` if
+    /// absent) and returns the new one; it may be called more than once if
+    /// another writer wins a race, so it should be pure/cheap.
+    fn update_t<T: Serialize + DeserializeOwned>(&self, key: &[u8], f: impl Fn(Option<T>) -> T) -> Result<T> {
+        loop {
+            let current_bytes = self.get(key);
+            let current: Option<T> = match &current_bytes {
+                Some(b) => Some(bincode::deserialize(b).with_context(|| "bincode deserialize")?),
+                None => None,
+            };
+            let next = f(current);
+            let next_bytes = bincode::serialize(&next).with_context(|| "bincode serialize")?;
+            if self.compare_and_swap(key, current_bytes.as_deref(), &next_bytes) {
+                return Ok(next);
+            }
+            std::thread::yield_now();
+        }
+    }
 }
 impl<T: Kv> KvSerde for T {}
 
@@ -113,4 +151,88 @@ impl Kv for FsKv {
         let path = self.path_for(key);
         fs::remove_file(path).is_ok()
     }
+
+    fn compare_and_swap(&self, key: &[u8], expected: Option<&[u8]>, new: &[u8]) -> bool {
+        let path = self.path_for(key);
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        // Exclusive lock file guards the read-compare-rename sequence below;
+        // `create_new` fails if another writer already holds it, so a racing
+        // CAS just reports failure (the caller's update_t loop retries).
+        let lock_path = path.with_extension("lock");
+        let Some(_lock) = LockGuard::acquire(&lock_path) else {
+            return false;
+        };
+
+        let current = fs::File::open(&path)
+            .ok()
+            .and_then(|mut f| {
+                let mut buf = Vec::new();
+                f.read_to_end(&mut buf).ok()?;
+                Some(buf)
+            });
+        let matches = current.as_deref() == expected;
+
+        let swapped = matches && {
+            let tmp = path.with_extension("tmp");
+            fs::File::create(&tmp)
+                .and_then(|mut f| {
+                    f.write_all(new)?;
+                    f.sync_all()
+                })
+                .and_then(|_| fs::rename(&tmp, &path))
+                .is_ok()
+        };
+
+        swapped
+        // `_lock` drops here, removing the lock file on every exit path above
+        // (including a panic unwinding through this frame).
+    }
+}
+
+/// How long a `.lock` file must sit untouched before [`LockGuard::acquire`]
+/// treats it as abandoned (owner crashed or was killed) rather than held by a
+/// live writer, and breaks it. A live CAS holds the lock for a single
+/// read-compare-rename, so this is generous slack, not a normal hold time.
+const STALE_LOCK_MS: u64 = 30_000;
+
+/// Holds a CAS lock file for the duration of a [`FsKv::compare_and_swap`]
+/// call, removing it on drop so a panic mid-CAS can't wedge the key forever.
+/// Doesn't help against `SIGKILL`/process crash (no destructor runs), which
+/// is why `acquire` also breaks locks older than [`STALE_LOCK_MS`].
+struct LockGuard(PathBuf);
+
+impl LockGuard {
+    fn acquire(lock_path: &Path) -> Option<Self> {
+        if Self::try_create(lock_path) {
+            return Some(Self(lock_path.to_path_buf()));
+        }
+        if Self::is_stale(lock_path) {
+            let _ = fs::remove_file(lock_path);
+            if Self::try_create(lock_path) {
+                return Some(Self(lock_path.to_path_buf()));
+            }
+        }
+        None
+    }
+
+    fn try_create(lock_path: &Path) -> bool {
+        fs::OpenOptions::new().write(true).create_new(true).open(lock_path).is_ok()
+    }
+
+    fn is_stale(lock_path: &Path) -> bool {
+        fs::metadata(lock_path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|m| m.elapsed().ok())
+            .is_some_and(|age| age.as_millis() as u64 > STALE_LOCK_MS)
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
 }